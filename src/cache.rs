@@ -0,0 +1,87 @@
+use console::{Emoji, style};
+use indicatif::HumanBytes;
+use std::fs;
+
+use crate::config::cli::CacheCommand;
+use crate::infra::cache::{DownloadCache, cache_dir};
+use crate::infra::err;
+
+const PACKAGE: Emoji<'_, '_> = Emoji("📦 ", "# ");
+const DIRECTORY: Emoji<'_, '_> = Emoji("📁 ", "* ");
+const DONE: Emoji<'_, '_> = Emoji("✨ ", "* ");
+
+/// Inspect or clean the local cache
+pub fn cache(command: CacheCommand) {
+    let cache_dir = match cache_dir() {
+        Some(cache_dir) => cache_dir,
+        None => err::abort_suggest_issue("Unable to find the cache directory"),
+    };
+
+    match command {
+        CacheCommand::List => list_cache(DownloadCache::new(cache_dir.join("downloads"), 0)),
+        CacheCommand::Clean => {
+            // only downloaded assets are removed, cached release metadata is
+            // small and saves GitHub API requests
+            let download_cache = DownloadCache::new(cache_dir.join("downloads"), 0);
+            let size = download_cache
+                .entries()
+                .map(|entries| entries.iter().map(|entry| entry.size).sum())
+                .unwrap_or(0);
+
+            match fs::remove_dir_all(download_cache.dir()) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => err::abort_with(format!(
+                    "Unable to remove the cache directory {}: {}",
+                    download_cache.dir().display(),
+                    e
+                )),
+            }
+
+            eprintln!("{} Removed {} of cached assets", DONE, HumanBytes(size));
+        }
+    }
+}
+
+fn list_cache(download_cache: DownloadCache) {
+    let entries = match download_cache.entries() {
+        Ok(entries) => entries,
+        Err(e) => err::abort_with(format!(
+            "Unable to read the cache directory {}: {}",
+            download_cache.dir().display(),
+            e
+        )),
+    };
+
+    for entry in &entries {
+        let name = entry
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        println!(
+            "{:>12}  {}",
+            HumanBytes(entry.size).to_string(),
+            style(name).cyan()
+        );
+    }
+
+    let total_size: u64 = entries.iter().map(|entry| entry.size).sum();
+    eprintln!(
+        "{} {} cached {}, {} in total",
+        PACKAGE,
+        entries.len(),
+        if entries.len() == 1 {
+            "asset"
+        } else {
+            "assets"
+        },
+        HumanBytes(total_size)
+    );
+    eprintln!(
+        "{} Cache directory: {}",
+        DIRECTORY,
+        download_cache.dir().display()
+    );
+}
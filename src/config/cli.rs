@@ -2,6 +2,8 @@ use std::path::PathBuf;
 
 use clap::{Parser, Subcommand};
 
+use crate::model::os::OS;
+
 #[derive(Parser, Debug)]
 #[command(author="Dmitrii Kovanikov <kovanikov@gmail.com>", version, about="A CLI tool to manage other CLI tools", long_about = None)]
 pub struct Cli {
@@ -13,6 +15,14 @@ pub struct Cli {
     #[arg(short, long, value_name = "uri")]
     pub proxy: Option<String>,
 
+    /// Wait until the GitHub API rate limit is reset instead of failing
+    #[arg(long)]
+    pub wait_on_rate_limit: bool,
+
+    /// Create missing store directories instead of failing
+    #[arg(long)]
+    pub create_dirs: bool,
+
     #[command(subcommand)]
     pub command: Command,
 }
@@ -72,7 +82,13 @@ pub enum Command {
     },
 
     /// Sync all tools specified in configuration file or the only one specified in the command line
-    Sync { tool: Option<String> },
+    Sync {
+        tool: Option<String>,
+
+        /// Install tools only from the download cache without network access
+        #[arg(long)]
+        offline: bool,
+    },
 
     /// Print a default .tool.toml configuration to std out
     DefaultConfig {
@@ -83,4 +99,60 @@ pub enum Command {
 
     /// Install a tool if it is hardcoded into internal database
     Install { name: String },
+
+    /// Show recent releases of a tool and whether they have an asset for this OS
+    Releases {
+        name: String,
+
+        /// Maximum number of releases to show
+        #[arg(long, value_name = "number", default_value_t = 10)]
+        limit: usize,
+
+        /// Show the first line of the release notes under each release
+        #[arg(long)]
+        notes: bool,
+    },
+
+    /// Download assets of all configured tools into a single bundle for installing them without network access
+    Bundle {
+        /// Path to the created bundle
+        output: PathBuf,
+
+        /// OS to bundle assets for (the current OS by default), can be repeated
+        #[arg(long = "os", value_name = "os")]
+        oses: Vec<OS>,
+    },
+
+    /// Install tools for the current OS from a bundle created by the 'bundle' command
+    ImportBundle { bundle: PathBuf },
+
+    /// Restore the previously installed version of a tool without network access
+    Rollback {
+        name: String,
+
+        /// Pin the restored tag in the configuration file
+        #[arg(long)]
+        pin: bool,
+    },
+
+    /// Remove an installed tool from the directory it was installed into
+    Uninstall { name: String },
+
+    /// Check the setup and report problems like tools missing from $PATH
+    Doctor,
+
+    /// Manage the cache of downloaded assets and release info
+    Cache {
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheCommand {
+    /// List downloaded assets stored in the cache
+    List,
+
+    /// Remove all cached assets
+    Clean,
 }
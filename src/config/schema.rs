@@ -1,10 +1,21 @@
-use shellexpand;
+use console::Emoji;
+use shellexpand::{self, LookupError};
 use std::collections::BTreeMap;
-use std::path::PathBuf;
+use std::env::VarError;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use crate::err;
+use crate::infra::cache::DownloadCache;
+use crate::infra::env_path;
 use crate::model::asset_name::AssetName;
-use crate::model::tool::{ToolInfo, ToolInfoTag};
+use crate::model::tool::{Executable, Extras, ToolInfo, ToolInfoTag};
+use crate::sync::state::versions_path;
+
+const WARNING: Emoji<'_, '_> = Emoji("⚠️  ", "! ");
+
+/// Default maximum size of the download cache in megabytes
+pub const DEFAULT_MAX_CACHE_SIZE_MB: u32 = 1024;
 
 /// Stores global information about the tool installation process and detailed
 /// info about installing each particular tool.
@@ -16,10 +27,61 @@ pub struct Config {
     pub store_directory: String,
 
     pub proxy: Option<String>,
+
+    /// How many times to retry requests failed due to network problems
+    pub retries: u32,
+
+    /// Whether to wait until the GitHub API rate limit is reset instead of failing
+    pub wait_on_rate_limit: bool,
+
+    /// Whether to create missing store directories instead of failing
+    pub create_store_directory: bool,
+
+    /// Whether to keep executables of each version in their own directory and
+    /// link the active one from the store directory
+    pub versioned_installs: bool,
+
+    /// Maximum size of the download cache in megabytes (0 disables the cache)
+    pub max_cache_size_mb: u32,
+
+    /// Where to install man pages and completions shipped with tools
+    pub extras_directories: ExtrasDirectories,
+
     /// Info about each individual tool
     pub tools: BTreeMap<String, ConfigAsset>,
 }
 
+/// Directories for man pages and shell completions. Extras of a kind are
+/// installed only when its directory is specified.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExtrasDirectories<Dir = String> {
+    pub man: Option<Dir>,
+    pub bash: Option<Dir>,
+    pub zsh: Option<Dir>,
+    pub fish: Option<Dir>,
+}
+
+impl ExtrasDirectories {
+    /// Shellexpand all specified directories
+    pub fn expand(&self) -> ExtrasDirectories<PathBuf> {
+        let expand = |directory: &Option<String>| {
+            directory
+                .as_deref()
+                .map(|directory| match expand_directory(directory) {
+                    Err(e) => err::abort_with(e),
+                    Ok(directory) => directory,
+                })
+        };
+
+        ExtrasDirectories {
+            man: expand(&self.man),
+            bash: expand(&self.bash),
+            zsh: expand(&self.zsh),
+            fish: expand(&self.fish),
+        }
+    }
+}
+
 /// Additional details, telling how to download a tool
 #[derive(Debug, PartialEq, Eq)]
 pub struct ConfigAsset {
@@ -33,30 +95,64 @@ pub struct ConfigAsset {
     /// Defaults to `repo` if not specified
     pub exe_name: Option<String>,
 
+    /// Name of the installed executable if it differs from 'exe_name'
+    pub bin_name: Option<String>,
+
+    /// Several executables to install from the asset instead of 'exe_name'
+    pub exe_names: Vec<Executable>,
+
     /// Release tag to download
     /// Defaults to the latest release
     pub tag: Option<String>,
 
+    /// Whether to download the newest release even if it's a prerelease
+    /// Ignored when `tag` or `channel` is specified
+    pub prerelease: bool,
+
+    /// Moving release tag to follow (e.g. "nightly")
+    /// The tool is redownloaded only when the assets of this tag change
+    pub channel: Option<String>,
+
     /// Name of the specific asset to download
     pub asset_name: AssetName,
 
+    /// Directory to store this tool instead of the global 'store_directory'
+    /// A relative path is a subdirectory of the global one
+    pub store_directory: Option<String>,
+
+    /// Path of the executable inside the archive as a glob with '{exe}' and
+    /// '{version}' placeholders, e.g. "*/bin/{exe}"
+    pub exe_path: Option<String>,
+
+    /// Man pages and completions inside the asset
+    pub extras: Extras,
+
     /// Proxy which will get used for all communication
     pub proxy: Option<ureq::Proxy>,
 }
 
 impl From<ToolInfo> for ConfigAsset {
     fn from(tool_info: ToolInfo) -> Self {
-        let tag = match tool_info.tag {
-            ToolInfoTag::Specific(version) => Some(version),
-            ToolInfoTag::Latest => None,
+        let (tag, prerelease, channel) = match tool_info.tag {
+            ToolInfoTag::Specific(version) => (Some(version), false, None),
+            ToolInfoTag::Channel(channel) => (None, false, Some(channel)),
+            ToolInfoTag::Prerelease => (None, true, None),
+            ToolInfoTag::Latest => (None, false, None),
         };
 
         Self {
             owner: Some(tool_info.owner),
             repo: Some(tool_info.repo),
             exe_name: Some(tool_info.exe_name),
+            bin_name: tool_info.bin_name,
+            exe_names: tool_info.exe_names,
             tag,
+            prerelease,
+            channel,
             asset_name: tool_info.asset_name,
+            store_directory: None,
+            exe_path: tool_info.exe_path,
+            extras: tool_info.extras,
 
             // Hardcoded tools don't supply their own proxy automatically
             proxy: None,
@@ -64,26 +160,137 @@ impl From<ToolInfo> for ConfigAsset {
     }
 }
 
+impl ConfigAsset {
+    /// Shellexpands the tool's own store directory like
+    /// `Config::ensure_store_directory` if it's specified. A relative path is
+    /// a subdirectory of the global 'store_directory'.
+    pub fn ensure_store_directory(
+        &self,
+        store_directory: &Path,
+        create: bool,
+    ) -> Option<Result<PathBuf, String>> {
+        self.expand_store_directory(store_directory)
+            .map(|directory| {
+                let directory = directory.map_err(|e| e.to_string())?;
+                prepare_directory(directory, create)
+            })
+    }
+
+    /// Shellexpand the tool's own store directory relative to the global one
+    pub fn expand_store_directory(
+        &self,
+        store_directory: &Path,
+    ) -> Option<Result<PathBuf, LookupError<VarError>>> {
+        self.store_directory.as_deref().map(|directory| {
+            expand_directory(directory).map(|directory| store_directory.join(directory))
+        })
+    }
+}
+
 impl Config {
     /// Shellexpands store directory, check whether it exists and exits with
-    /// error if 'store_directory' doesn't exist
+    /// error if 'store_directory' doesn't exist (or can't be created when
+    /// 'create_store_directory' is enabled)
     pub fn ensure_store_directory(&self) -> PathBuf {
-        let expanded_store_directory = shellexpand::full(&self.store_directory);
-
-        let store_directory = match expanded_store_directory {
-            Err(e) => err::abort_with(e),
-            Ok(cow_path) => PathBuf::from(cow_path.into_owned()),
-        };
+        ensure_directory(&self.store_directory, self.create_store_directory)
+    }
 
-        let has_store_directory = store_directory.as_path().is_dir();
+    /// Directory for the versioned install layout or `None` if it's disabled
+    pub fn versions_directory(&self) -> Option<PathBuf> {
+        if self.versioned_installs {
+            versions_path()
+        } else {
+            None
+        }
+    }
 
-        if !has_store_directory {
-            err::abort_with(format!(
-                "Specified directory for storing tools doesn't exist: {}",
-                store_directory.display()
-            ));
+    /// Download cache limited to the configured size or `None` if disabled
+    pub fn download_cache(&self) -> Option<DownloadCache> {
+        match self.max_cache_size_mb {
+            0 => None,
+            size_mb => DownloadCache::open(u64::from(size_mb) * 1024 * 1024),
         }
+    }
+}
+
+/// Expand the tilde `~` and environment variables in the directory path
+pub fn expand_directory(directory: &str) -> Result<PathBuf, LookupError<VarError>> {
+    shellexpand::full(directory).map(|cow_path| PathBuf::from(cow_path.into_owned()))
+}
+
+/// Shellexpand the directory path, create it if 'create' is enabled, and exit
+/// with error if it doesn't exist or isn't writable
+fn ensure_directory(
+    directory: &str,
+    create: bool,
+) -> PathBuf {
+    let directory = match expand_directory(directory) {
+        Err(e) => err::abort_with(e),
+        Ok(directory) => directory,
+    };
+
+    match prepare_directory(directory, create) {
+        Err(e) => err::abort_with(e),
+        Ok(directory) => directory,
+    }
+}
 
-        store_directory
+/// Create the directory if 'create' is enabled. A newly created directory is
+/// checked for being writable and listed in $PATH, existing ones are checked
+/// only by 'tool doctor' to not repeat the warning on every run.
+fn prepare_directory(
+    directory: PathBuf,
+    create: bool,
+) -> Result<PathBuf, String> {
+    if directory.as_path().is_dir() {
+        return Ok(directory);
     }
+
+    if !create {
+        return Err(format!(
+            r#"Specified directory for storing tools doesn't exist: {}
+
+Create it manually or let 'tool-sync' create it by setting
+'create_store_directory = true' in the config or using the '--create-dirs' option"#,
+            directory.display()
+        ));
+    }
+
+    if let Err(e) = fs::create_dir_all(&directory) {
+        return Err(format!(
+            "Unable to create directory for storing tools {}: {}",
+            directory.display(),
+            e
+        ));
+    }
+
+    if let Err(e) = tempfile::tempfile_in(&directory) {
+        return Err(format!(
+            "Directory for storing tools isn't writable {}: {}",
+            directory.display(),
+            e
+        ));
+    }
+
+    if !env_path::is_on_path(&directory, &env_path::path_dirs()) {
+        not_on_path_warning(&directory);
+    }
+
+    Ok(directory)
+}
+
+fn not_on_path_warning(directory: &Path) {
+    let shell = env_path::current_shell();
+
+    eprintln!(
+        r#"{warning}The directory {directory} isn't listed in $PATH, so installed tools can't be run by name.
+Add it to $PATH by putting the following line into {profile}:
+
+    {snippet}
+"#,
+        warning = WARNING,
+        directory = directory.display(),
+        profile = env_path::shell_profile(shell.as_deref()),
+        snippet = env_path::add_to_path_snippet(directory, shell.as_deref()),
+    );
 }
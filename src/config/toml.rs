@@ -1,19 +1,22 @@
 use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use toml::{Value, map::Map};
 
-use crate::config::schema::{Config, ConfigAsset};
+use crate::config::schema::{Config, ConfigAsset, DEFAULT_MAX_CACHE_SIZE_MB, ExtrasDirectories};
 use crate::infra::err;
+use crate::infra::retry::DEFAULT_RETRIES;
 use crate::model::asset_name::AssetName;
 use crate::model::os::OS;
+use crate::model::tool::{Completions, Executable, Extras};
 
 #[derive(Debug, PartialEq)]
 pub enum TomlError {
     IO(String),
     Parse(toml::de::Error),
     Decode(DecodeError),
+    Edit(String),
 }
 
 #[derive(Debug, PartialEq)]
@@ -37,6 +40,7 @@ impl Display for TomlError {
             TomlError::IO(e) => write!(f, "[IO Error] {}", e),
             TomlError::Parse(e) => write!(f, "[Parsing Error] {}", e),
             TomlError::Decode(e) => write!(f, "[Decode Error] {}", e),
+            TomlError::Edit(e) => write!(f, "[Edit Error] {}", e),
         }
     }
 }
@@ -69,10 +73,15 @@ impl Display for DecodeError {
 pub fn with_parsed_file<F: FnOnce(Config)>(
     config_path: PathBuf,
     proxy: Option<String>,
+    wait_on_rate_limit: bool,
+    create_store_directory: bool,
     on_success: F,
 ) {
     match parse_file(&config_path, proxy) {
-        Ok(config) => {
+        Ok(mut config) => {
+            // the command-line flags can only enable options
+            config.wait_on_rate_limit |= wait_on_rate_limit;
+            config.create_store_directory |= create_store_directory;
             on_success(config);
         }
         Err(e) => {
@@ -85,7 +94,47 @@ pub fn with_parsed_file<F: FnOnce(Config)>(
     }
 }
 
-fn parse_file(
+/// Set the tag of a tool in the configuration file, keeping the rest of the
+/// file including comments as is
+pub fn pin_tag(
+    config_path: &Path,
+    tool_name: &str,
+    tag: &str,
+) -> Result<(), TomlError> {
+    let contents = fs::read_to_string(config_path).map_err(|e| TomlError::IO(format!("{}", e)))?;
+    let pinned = pin_tag_in_string(&contents, tool_name, tag)?;
+    fs::write(config_path, pinned).map_err(|e| TomlError::IO(format!("{}", e)))
+}
+
+fn pin_tag_in_string(
+    contents: &str,
+    tool_name: &str,
+    tag: &str,
+) -> Result<String, TomlError> {
+    let mut doc: toml_edit::DocumentMut = contents
+        .parse()
+        .map_err(|e: toml_edit::TomlError| TomlError::Edit(e.to_string()))?;
+
+    let tool = doc.entry(tool_name).or_insert(toml_edit::table());
+    match tool.as_table_like_mut() {
+        Some(tool) => {
+            // replacing only the value keeps comments attached to the key
+            match tool.get_mut("tag") {
+                Some(item) => *item = toml_edit::value(tag),
+                None => {
+                    tool.insert("tag", toml_edit::value(tag));
+                }
+            }
+            Ok(doc.to_string())
+        }
+        None => Err(TomlError::Edit(format!(
+            "The tool '{}' is not a table",
+            tool_name
+        ))),
+    }
+}
+
+pub fn parse_file(
     config_path: &PathBuf,
     proxy: Option<String>,
 ) -> Result<Config, TomlError> {
@@ -127,6 +176,20 @@ fn decode_config(
     let proxy: Option<String> =
         proxy.or_else(|| toml.get("proxy").map(|p| p.as_str().unwrap_or("").into()));
 
+    let retries = uint_by_key(&toml, "retries", DEFAULT_RETRIES)?;
+    let max_cache_size_mb = uint_by_key(&toml, "max_cache_size_mb", DEFAULT_MAX_CACHE_SIZE_MB)?;
+
+    let wait_on_rate_limit = flag_by_key(&toml, "wait_on_rate_limit")?;
+    let create_store_directory = flag_by_key(&toml, "create_store_directory")?;
+    let versioned_installs = flag_by_key(&toml, "versioned_installs")?;
+
+    let extras_directories = ExtrasDirectories {
+        man: optional_str_by_key(&toml, "man_directory")?,
+        bash: optional_str_by_key(&toml, "bash_completion_directory")?,
+        zsh: optional_str_by_key(&toml, "zsh_completion_directory")?,
+        fish: optional_str_by_key(&toml, "fish_completion_directory")?,
+    };
+
     let mut tools = BTreeMap::new();
 
     let table = toml
@@ -135,7 +198,7 @@ fn decode_config(
 
     for (key, val) in table.iter() {
         if let Value::Table(table) = val {
-            tools.insert(key.clone(), decode_config_asset(table, &proxy));
+            tools.insert(key.clone(), decode_config_asset(key, table, &proxy)?);
         }
     }
 
@@ -143,25 +206,110 @@ fn decode_config(
         store_directory,
         tools,
         proxy,
+        retries,
+        wait_on_rate_limit,
+        create_store_directory,
+        versioned_installs,
+        max_cache_size_mb,
+        extras_directories,
     })
 }
 
+/// Decode an optional boolean option which is disabled by default
+fn flag_by_key(
+    toml: &Value,
+    key: &str,
+) -> Result<bool, DecodeError> {
+    match toml.get(key) {
+        None => Ok(false),
+        Some(value) => value.as_bool().ok_or_else(|| DecodeError::InvalidType {
+            key: String::from(key),
+            expected: Value::Boolean(true),
+            found: value.clone(),
+        }),
+    }
+}
+
+/// Decode an optional string option
+fn optional_str_by_key(
+    toml: &Value,
+    key: &str,
+) -> Result<Option<String>, DecodeError> {
+    optional_str(toml.get(key), key)
+}
+
+/// Decode an optional string value, 'key' is its full name for errors
+fn optional_str(
+    value: Option<&Value>,
+    key: &str,
+) -> Result<Option<String>, DecodeError> {
+    value.map(|value| str_value(value, key)).transpose()
+}
+
+fn str_value(
+    value: &Value,
+    key: &str,
+) -> Result<String, DecodeError> {
+    match value {
+        Value::String(value) => Ok(value.clone()),
+        other => Err(DecodeError::InvalidType {
+            key: String::from(key),
+            expected: Value::String("some_value".into()),
+            found: other.clone(),
+        }),
+    }
+}
+
+/// Decode an optional non-negative integer
+fn uint_by_key<T: TryFrom<i64> + Into<i64>>(
+    toml: &Value,
+    key: &str,
+    default: T,
+) -> Result<T, DecodeError> {
+    match toml.get(key) {
+        None => Ok(default),
+        Some(value) => value
+            .as_integer()
+            .and_then(|n| T::try_from(n).ok())
+            .ok_or_else(|| DecodeError::InvalidType {
+                key: String::from(key),
+                expected: Value::Integer(default.into()),
+                found: value.clone(),
+            }),
+    }
+}
+
 fn decode_config_asset(
+    tool_name: &str,
     table: &Map<String, Value>,
     proxy: &Option<String>,
-) -> ConfigAsset {
+) -> Result<ConfigAsset, DecodeError> {
     let owner = str_by_key(table, "owner");
     let repo = str_by_key(table, "repo");
     let exe_name = str_by_key(table, "exe_name");
+    let bin_name = str_by_key(table, "bin_name");
+    let exe_names = decode_exe_names(tool_name, table)?;
     let asset_name = decode_asset_name(table);
     let tag = str_by_key(table, "tag");
+    let prerelease = bool_by_key(table, "prerelease").unwrap_or(false);
+    let channel = str_by_key(table, "channel");
+    let store_directory = str_by_key(table, "store_directory");
+    let exe_path = str_by_key(table, "exe_path");
+    let extras = decode_extras(tool_name, table)?;
 
     let mut config_asset = ConfigAsset {
         owner,
         repo,
         exe_name,
+        bin_name,
+        exe_names,
         asset_name,
         tag,
+        prerelease,
+        channel,
+        store_directory,
+        exe_path,
+        extras,
         proxy: None,
     };
     if let Some(p) = proxy {
@@ -172,7 +320,100 @@ fn decode_config_asset(
             )
         }));
     };
-    config_asset
+    Ok(config_asset)
+}
+
+/// Decode a list of executables, each one is either a name or a table with
+/// the 'exe_name' and optional 'bin_name' to rename it
+fn decode_exe_names(
+    tool_name: &str,
+    table: &Map<String, Value>,
+) -> Result<Vec<Executable>, DecodeError> {
+    let key = format!("{}.exe_names", tool_name);
+
+    match table.get("exe_names") {
+        None => Ok(vec![]),
+        Some(Value::Array(exe_names)) => exe_names
+            .iter()
+            .map(|exe_name| decode_executable(&key, exe_name))
+            .collect(),
+        Some(other) => Err(DecodeError::InvalidType {
+            key,
+            expected: Value::Array(vec![]),
+            found: other.clone(),
+        }),
+    }
+}
+
+fn decode_executable(
+    key: &str,
+    value: &Value,
+) -> Result<Executable, DecodeError> {
+    match value {
+        Value::String(exe_name) => Ok(Executable::new(exe_name)),
+        Value::Table(table) => {
+            let exe_name_key = format!("{}.exe_name", key);
+            let exe_name = optional_str(table.get("exe_name"), &exe_name_key)?
+                .ok_or(DecodeError::MissingKey { key: exe_name_key })?;
+            let bin_name = optional_str(table.get("bin_name"), &format!("{}.bin_name", key))?
+                .unwrap_or_else(|| exe_name.clone());
+            Ok(Executable { exe_name, bin_name })
+        }
+        other => Err(DecodeError::InvalidType {
+            key: String::from(key),
+            expected: Value::String("some_value".into()),
+            found: other.clone(),
+        }),
+    }
+}
+
+fn decode_extras(
+    tool_name: &str,
+    table: &Map<String, Value>,
+) -> Result<Extras, DecodeError> {
+    let man_pages_key = format!("{}.man_pages", tool_name);
+    let man_pages = match table.get("man_pages") {
+        None => vec![],
+        Some(Value::Array(man_pages)) => man_pages
+            .iter()
+            .map(|man_page| str_value(man_page, &man_pages_key))
+            .collect::<Result<_, _>>()?,
+        Some(other) => {
+            return Err(DecodeError::InvalidType {
+                key: man_pages_key,
+                expected: Value::Array(vec![]),
+                found: other.clone(),
+            });
+        }
+    };
+
+    let completions_key = format!("{}.completions", tool_name);
+    let completions = match table.get("completions") {
+        None => Completions::default(),
+        Some(Value::Table(table)) => {
+            let completion = |shell: &str| {
+                optional_str(table.get(shell), &format!("{}.{}", completions_key, shell))
+            };
+
+            Completions {
+                bash: completion("bash")?,
+                zsh: completion("zsh")?,
+                fish: completion("fish")?,
+            }
+        }
+        Some(other) => {
+            return Err(DecodeError::InvalidType {
+                key: completions_key,
+                expected: Value::Table(Map::new()),
+                found: other.clone(),
+            });
+        }
+    };
+
+    Ok(Extras {
+        man_pages,
+        completions,
+    })
 }
 
 fn decode_asset_name(table: &Map<String, Value>) -> AssetName {
@@ -204,6 +445,13 @@ fn str_by_key(
     table.get(key).and_then(|v| v.as_str()).map(String::from)
 }
 
+fn bool_by_key(
+    table: &Map<String, Value>,
+    key: &str,
+) -> Option<bool> {
+    table.get(key).and_then(|v| v.as_bool())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -330,6 +578,12 @@ mod tests {
             store_directory: String::from("pancake"),
             tools: BTreeMap::new(),
             proxy: None,
+            retries: DEFAULT_RETRIES,
+            wait_on_rate_limit: false,
+            create_store_directory: false,
+            versioned_installs: false,
+            extras_directories: ExtrasDirectories::default(),
+            max_cache_size_mb: DEFAULT_MAX_CACHE_SIZE_MB,
         };
 
         assert_eq!(res, Ok(cfg));
@@ -353,16 +607,29 @@ mod tests {
                     owner: None,
                     repo: None,
                     exe_name: None,
+                    bin_name: None,
+                    exe_names: vec![],
                     asset_name: AssetName {
                         linux: None,
                         macos: None,
                         windows: None,
                     },
                     tag: None,
+                    prerelease: false,
+                    channel: None,
+                    store_directory: None,
+                    exe_path: None,
+                    extras: Extras::default(),
                     proxy: None,
                 },
             )]),
             proxy: None,
+            retries: DEFAULT_RETRIES,
+            wait_on_rate_limit: false,
+            create_store_directory: false,
+            versioned_installs: false,
+            extras_directories: ExtrasDirectories::default(),
+            max_cache_size_mb: DEFAULT_MAX_CACHE_SIZE_MB,
         };
 
         assert_eq!(res, Ok(cfg));
@@ -388,12 +655,19 @@ mod tests {
                         owner: None,
                         repo: None,
                         exe_name: None,
+                        bin_name: None,
+                        exe_names: vec![],
                         asset_name: AssetName {
                             linux: None,
                             macos: None,
                             windows: None,
                         },
                         tag: None,
+                        prerelease: false,
+                        channel: None,
+                        store_directory: None,
+                        exe_path: None,
+                        extras: Extras::default(),
                         proxy: None,
                     },
                 ),
@@ -403,17 +677,30 @@ mod tests {
                         owner: None,
                         repo: None,
                         exe_name: None,
+                        bin_name: None,
+                        exe_names: vec![],
                         asset_name: AssetName {
                             linux: None,
                             macos: None,
                             windows: None,
                         },
                         tag: None,
+                        prerelease: false,
+                        channel: None,
+                        store_directory: None,
+                        exe_path: None,
+                        extras: Extras::default(),
                         proxy: None,
                     },
                 ),
             ]),
             proxy: None,
+            retries: DEFAULT_RETRIES,
+            wait_on_rate_limit: false,
+            create_store_directory: false,
+            versioned_installs: false,
+            extras_directories: ExtrasDirectories::default(),
+            max_cache_size_mb: DEFAULT_MAX_CACHE_SIZE_MB,
         };
 
         assert_eq!(res, Ok(cfg));
@@ -439,16 +726,29 @@ mod tests {
                     owner: Some("me".to_owned()),
                     repo: None,
                     exe_name: None,
+                    bin_name: None,
+                    exe_names: vec![],
                     asset_name: AssetName {
                         linux: Some("R2D2".to_owned()),
                         macos: None,
                         windows: None,
                     },
                     tag: None,
+                    prerelease: false,
+                    channel: None,
+                    store_directory: None,
+                    exe_path: None,
+                    extras: Extras::default(),
                     proxy: None,
                 },
             )]),
             proxy: None,
+            retries: DEFAULT_RETRIES,
+            wait_on_rate_limit: false,
+            create_store_directory: false,
+            versioned_installs: false,
+            extras_directories: ExtrasDirectories::default(),
+            max_cache_size_mb: DEFAULT_MAX_CACHE_SIZE_MB,
         };
 
         assert_eq!(res, Ok(cfg));
@@ -479,18 +779,541 @@ mod tests {
                     owner: Some("me".to_owned()),
                     repo: Some("some_repo".to_owned()),
                     exe_name: Some("rg".to_owned()),
+                    bin_name: None,
+                    exe_names: vec![],
                     asset_name: AssetName {
                         linux: Some("R2D2".to_owned()),
                         macos: Some("C3-PO".to_owned()),
                         windows: Some("IG-88".to_owned()),
                     },
                     tag: Some("4.2.0".to_owned()),
+                    prerelease: false,
+                    channel: None,
+                    store_directory: None,
+                    exe_path: None,
+                    extras: Extras::default(),
+                    proxy: None,
+                },
+            )]),
+            proxy: None,
+            retries: DEFAULT_RETRIES,
+            wait_on_rate_limit: false,
+            create_store_directory: false,
+            versioned_installs: false,
+            extras_directories: ExtrasDirectories::default(),
+            max_cache_size_mb: DEFAULT_MAX_CACHE_SIZE_MB,
+        };
+
+        assert_eq!(res, Ok(cfg));
+    }
+
+    #[test]
+    fn prerelease_and_channel() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [neovim]
+            prerelease = true
+            channel = "nightly"
+        "#;
+
+        let res = parse_string(toml, None);
+
+        let cfg = Config {
+            store_directory: String::from("pancake"),
+            tools: BTreeMap::from([(
+                "neovim".to_owned(),
+                ConfigAsset {
+                    owner: None,
+                    repo: None,
+                    exe_name: None,
+                    bin_name: None,
+                    exe_names: vec![],
+                    asset_name: AssetName {
+                        linux: None,
+                        macos: None,
+                        windows: None,
+                    },
+                    tag: None,
+                    prerelease: true,
+                    channel: Some("nightly".to_owned()),
+                    store_directory: None,
+                    exe_path: None,
+                    extras: Extras::default(),
+                    proxy: None,
+                },
+            )]),
+            proxy: None,
+            retries: DEFAULT_RETRIES,
+            wait_on_rate_limit: false,
+            create_store_directory: false,
+            versioned_installs: false,
+            extras_directories: ExtrasDirectories::default(),
+            max_cache_size_mb: DEFAULT_MAX_CACHE_SIZE_MB,
+        };
+
+        assert_eq!(res, Ok(cfg));
+    }
+
+    #[test]
+    fn tool_store_directory() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [kubectl-krew]
+            store_directory = "~/.krew/bin"
+        "#;
+
+        let res = parse_string(toml, None);
+
+        let cfg = Config {
+            store_directory: String::from("pancake"),
+            tools: BTreeMap::from([(
+                "kubectl-krew".to_owned(),
+                ConfigAsset {
+                    owner: None,
+                    repo: None,
+                    exe_name: None,
+                    bin_name: None,
+                    exe_names: vec![],
+                    asset_name: AssetName {
+                        linux: None,
+                        macos: None,
+                        windows: None,
+                    },
+                    tag: None,
+                    prerelease: false,
+                    channel: None,
+                    store_directory: Some("~/.krew/bin".to_owned()),
+                    exe_path: None,
+                    extras: Extras::default(),
+                    proxy: None,
+                },
+            )]),
+            proxy: None,
+            retries: DEFAULT_RETRIES,
+            wait_on_rate_limit: false,
+            create_store_directory: false,
+            versioned_installs: false,
+            extras_directories: ExtrasDirectories::default(),
+            max_cache_size_mb: DEFAULT_MAX_CACHE_SIZE_MB,
+        };
+
+        assert_eq!(res, Ok(cfg));
+    }
+
+    #[test]
+    fn renamed_executable() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [difftastic]
+            bin_name = "difftastic"
+        "#;
+
+        let res = parse_string(toml, None);
+
+        let cfg = Config {
+            store_directory: String::from("pancake"),
+            tools: BTreeMap::from([(
+                "difftastic".to_owned(),
+                ConfigAsset {
+                    owner: None,
+                    repo: None,
+                    exe_name: None,
+                    bin_name: Some("difftastic".to_owned()),
+                    exe_names: vec![],
+                    asset_name: AssetName {
+                        linux: None,
+                        macos: None,
+                        windows: None,
+                    },
+                    tag: None,
+                    prerelease: false,
+                    channel: None,
+                    store_directory: None,
+                    exe_path: None,
+                    extras: Extras::default(),
                     proxy: None,
                 },
             )]),
             proxy: None,
+            retries: DEFAULT_RETRIES,
+            wait_on_rate_limit: false,
+            create_store_directory: false,
+            versioned_installs: false,
+            extras_directories: ExtrasDirectories::default(),
+            max_cache_size_mb: DEFAULT_MAX_CACHE_SIZE_MB,
         };
 
         assert_eq!(res, Ok(cfg));
     }
+
+    #[test]
+    fn exe_path_is_configured() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [tokei]
+            exe_path = "*/bin/{exe}"
+        "#;
+
+        let cfg = parse_string(toml, None).unwrap();
+
+        assert_eq!(
+            cfg.tools["tokei"].exe_path,
+            Some(String::from("*/bin/{exe}"))
+        );
+    }
+
+    #[test]
+    fn extras_are_configured() {
+        let toml = r#"
+            store_directory = "pancake"
+            man_directory = "~/.local/share/man"
+            zsh_completion_directory = "~/.zfunc"
+
+            [tokei]
+            man_pages = ["doc/tokei.1"]
+            completions.zsh = "completions/_tokei"
+        "#;
+
+        let cfg = parse_string(toml, None).unwrap();
+
+        assert_eq!(
+            cfg.extras_directories,
+            ExtrasDirectories {
+                man: Some(String::from("~/.local/share/man")),
+                bash: None,
+                zsh: Some(String::from("~/.zfunc")),
+                fish: None,
+            }
+        );
+        assert_eq!(
+            cfg.tools["tokei"].extras,
+            Extras {
+                man_pages: vec![String::from("doc/tokei.1")],
+                completions: Completions {
+                    bash: None,
+                    zsh: Some(String::from("completions/_tokei")),
+                    fish: None,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_extras() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [ripgrep]
+            man_pages = "doc/rg.1"
+        "#;
+
+        assert_eq!(
+            parse_string(toml, None),
+            Err(TomlError::Decode(DecodeError::InvalidType {
+                key: String::from("ripgrep.man_pages"),
+                expected: Value::Array(vec![]),
+                found: Value::String(String::from("doc/rg.1"))
+            }))
+        );
+
+        let toml = r#"
+            store_directory = "pancake"
+
+            [ripgrep]
+            completions.zsh = ["complete/_rg"]
+        "#;
+
+        assert_eq!(
+            parse_string(toml, None),
+            Err(TomlError::Decode(DecodeError::InvalidType {
+                key: String::from("ripgrep.completions.zsh"),
+                expected: Value::String("some_value".into()),
+                found: Value::Array(vec![Value::String(String::from("complete/_rg"))])
+            }))
+        );
+    }
+
+    #[test]
+    fn several_executables() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [age]
+            owner = "FiloSottile"
+            repo = "age"
+            exe_names = ["age", { exe_name = "age-keygen", bin_name = "keygen" }]
+        "#;
+
+        let res = parse_string(toml, None);
+
+        let cfg = Config {
+            store_directory: String::from("pancake"),
+            tools: BTreeMap::from([(
+                "age".to_owned(),
+                ConfigAsset {
+                    owner: Some("FiloSottile".to_owned()),
+                    repo: Some("age".to_owned()),
+                    exe_name: None,
+                    bin_name: None,
+                    exe_names: vec![
+                        Executable::new("age"),
+                        Executable {
+                            exe_name: "age-keygen".to_owned(),
+                            bin_name: "keygen".to_owned(),
+                        },
+                    ],
+                    asset_name: AssetName {
+                        linux: None,
+                        macos: None,
+                        windows: None,
+                    },
+                    tag: None,
+                    prerelease: false,
+                    channel: None,
+                    store_directory: None,
+                    exe_path: None,
+                    extras: Extras::default(),
+                    proxy: None,
+                },
+            )]),
+            proxy: None,
+            retries: DEFAULT_RETRIES,
+            wait_on_rate_limit: false,
+            create_store_directory: false,
+            versioned_installs: false,
+            extras_directories: ExtrasDirectories::default(),
+            max_cache_size_mb: DEFAULT_MAX_CACHE_SIZE_MB,
+        };
+
+        assert_eq!(res, Ok(cfg));
+    }
+
+    #[test]
+    fn invalid_executables() {
+        let toml = r#"
+            store_directory = "pancake"
+
+            [age]
+            exe_names = ["age", 42]
+        "#;
+
+        assert_eq!(
+            parse_string(toml, None),
+            Err(TomlError::Decode(DecodeError::InvalidType {
+                key: String::from("age.exe_names"),
+                expected: Value::String("some_value".into()),
+                found: Value::Integer(42)
+            }))
+        );
+
+        let toml = r#"
+            store_directory = "pancake"
+
+            [age]
+            exe_names = [{ bin_name = "keygen" }]
+        "#;
+
+        assert_eq!(
+            parse_string(toml, None),
+            Err(TomlError::Decode(DecodeError::MissingKey {
+                key: String::from("age.exe_names.exe_name"),
+            }))
+        );
+    }
+
+    #[test]
+    fn retries_are_configured() {
+        let toml = r#"
+            store_directory = "pancake"
+            retries = 5
+        "#;
+
+        let res = parse_string(toml, None);
+
+        let cfg = Config {
+            store_directory: String::from("pancake"),
+            tools: BTreeMap::new(),
+            proxy: None,
+            retries: 5,
+            wait_on_rate_limit: false,
+            create_store_directory: false,
+            versioned_installs: false,
+            extras_directories: ExtrasDirectories::default(),
+            max_cache_size_mb: DEFAULT_MAX_CACHE_SIZE_MB,
+        };
+
+        assert_eq!(res, Ok(cfg));
+    }
+
+    #[test]
+    fn retries_is_negative() {
+        let toml = r#"
+            store_directory = "pancake"
+            retries = -1
+        "#;
+
+        let res = parse_string(toml, None);
+
+        assert_eq!(
+            res,
+            Err(TomlError::Decode(DecodeError::InvalidType {
+                key: String::from("retries"),
+                expected: Value::Integer(3),
+                found: Value::Integer(-1)
+            }))
+        );
+    }
+
+    #[test]
+    fn wait_on_rate_limit_is_configured() {
+        let toml = r#"
+            store_directory = "pancake"
+            wait_on_rate_limit = true
+        "#;
+
+        let res = parse_string(toml, None);
+
+        let cfg = Config {
+            store_directory: String::from("pancake"),
+            tools: BTreeMap::new(),
+            proxy: None,
+            retries: DEFAULT_RETRIES,
+            wait_on_rate_limit: true,
+            create_store_directory: false,
+            versioned_installs: false,
+            extras_directories: ExtrasDirectories::default(),
+            max_cache_size_mb: DEFAULT_MAX_CACHE_SIZE_MB,
+        };
+
+        assert_eq!(res, Ok(cfg));
+    }
+
+    #[test]
+    fn create_store_directory_is_configured() {
+        let toml = r#"
+            store_directory = "pancake"
+            create_store_directory = true
+        "#;
+
+        let res = parse_string(toml, None);
+
+        let cfg = Config {
+            store_directory: String::from("pancake"),
+            tools: BTreeMap::new(),
+            proxy: None,
+            retries: DEFAULT_RETRIES,
+            wait_on_rate_limit: false,
+            create_store_directory: true,
+            versioned_installs: false,
+            extras_directories: ExtrasDirectories::default(),
+            max_cache_size_mb: DEFAULT_MAX_CACHE_SIZE_MB,
+        };
+
+        assert_eq!(res, Ok(cfg));
+    }
+
+    #[test]
+    fn create_store_directory_is_not_a_bool() {
+        let toml = r#"
+            store_directory = "pancake"
+            create_store_directory = "yes"
+        "#;
+
+        let res = parse_string(toml, None);
+
+        assert_eq!(
+            res,
+            Err(TomlError::Decode(DecodeError::InvalidType {
+                key: String::from("create_store_directory"),
+                expected: Value::Boolean(true),
+                found: Value::String(String::from("yes")),
+            }))
+        );
+    }
+
+    #[test]
+    fn versioned_installs_are_configured() {
+        let toml = r#"
+            store_directory = "pancake"
+            versioned_installs = true
+        "#;
+
+        let res = parse_string(toml, None);
+
+        let cfg = Config {
+            store_directory: String::from("pancake"),
+            tools: BTreeMap::new(),
+            proxy: None,
+            retries: DEFAULT_RETRIES,
+            wait_on_rate_limit: false,
+            create_store_directory: false,
+            versioned_installs: true,
+            extras_directories: ExtrasDirectories::default(),
+            max_cache_size_mb: DEFAULT_MAX_CACHE_SIZE_MB,
+        };
+
+        assert_eq!(res, Ok(cfg));
+    }
+
+    #[test]
+    fn tag_is_pinned() {
+        let toml = r#"# my tools
+store_directory = "pancake"
+
+[bat]
+[ripgrep]
+# stable only
+tag = "14.0.0"
+"#;
+
+        assert_eq!(
+            pin_tag_in_string(toml, "ripgrep", "13.0.0"),
+            Ok(String::from(
+                r#"# my tools
+store_directory = "pancake"
+
+[bat]
+[ripgrep]
+# stable only
+tag = "13.0.0"
+"#
+            ))
+        );
+
+        assert_eq!(
+            pin_tag_in_string(toml, "bat", "v0.22.1"),
+            Ok(String::from(
+                r#"# my tools
+store_directory = "pancake"
+
+[bat]
+tag = "v0.22.1"
+[ripgrep]
+# stable only
+tag = "14.0.0"
+"#
+            ))
+        );
+    }
+
+    #[test]
+    fn tag_is_pinned_for_new_tool() {
+        let toml = r#"store_directory = "pancake"
+"#;
+
+        assert_eq!(
+            pin_tag_in_string(toml, "fd", "v8.5.3"),
+            Ok(String::from(
+                r#"store_directory = "pancake"
+
+[fd]
+tag = "v8.5.3"
+"#
+            ))
+        );
+    }
 }
@@ -0,0 +1,513 @@
+use console::{Emoji, style};
+use std::env;
+use std::fmt::Display;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::config::schema::{Config, expand_directory};
+use crate::config::toml;
+use crate::infra::client::fetch_rate_limit;
+use crate::infra::env_path;
+use crate::infra::rate_limit::fmt_reset;
+use crate::model::tool::Tool;
+use crate::sync::configure::configure_tool;
+use crate::sync::state::InstallState;
+
+/// How long to wait for an installed executable to print its version
+const VERSION_TIMEOUT: Duration = Duration::from_secs(5);
+
+const OK: Emoji<'_, '_> = Emoji("✅ ", "OK ");
+const WARNING: Emoji<'_, '_> = Emoji("⚠️  ", "!  ");
+const FAILURE: Emoji<'_, '_> = Emoji("❌ ", "NO ");
+
+/// Checklist of diagnostics printed by the 'doctor' command
+struct Checklist {
+    problems: usize,
+}
+
+impl Checklist {
+    fn new() -> Checklist {
+        Checklist { problems: 0 }
+    }
+
+    fn section(
+        &self,
+        title: &str,
+    ) {
+        println!("\n{}", style(title).bold());
+    }
+
+    fn ok<Message: Display>(
+        &self,
+        msg: Message,
+    ) {
+        println!("{}{}", OK, msg);
+    }
+
+    fn warning<Message: Display>(
+        &mut self,
+        msg: Message,
+        hint: &str,
+    ) {
+        self.problems += 1;
+        println!("{}{}", WARNING, msg);
+        print_hint(hint);
+    }
+
+    fn failure<Message: Display>(
+        &mut self,
+        msg: Message,
+        hint: &str,
+    ) {
+        self.problems += 1;
+        println!("{}{}", FAILURE, style(msg).red());
+        print_hint(hint);
+    }
+}
+
+fn print_hint(hint: &str) {
+    for line in hint.lines() {
+        if line.is_empty() {
+            println!();
+        } else {
+            println!("     {}", line);
+        }
+    }
+}
+
+/// Check the 'tool-sync' setup and report problems with hints how to fix them
+pub fn doctor(
+    config_path: PathBuf,
+    proxy: Option<String>,
+) {
+    let mut checklist = Checklist::new();
+
+    checklist.section("Configuration");
+    let config = check_config(&mut checklist, &config_path, proxy.clone());
+
+    if let Some(config) = &config {
+        checklist.section("Tools");
+        check_tools(&mut checklist, config);
+    }
+
+    checklist.section("GitHub API");
+    check_token(&mut checklist);
+    // the '--proxy' option is used even if the configuration can't be parsed
+    let proxy = config.as_ref().map_or(proxy, |config| config.proxy.clone());
+    check_github_api(&mut checklist, proxy.as_deref());
+
+    if let Some(config) = &config {
+        checklist.section("Store directories");
+        check_store_directories(&mut checklist, config);
+    }
+
+    checklist.section("Installed tools");
+    check_installed_tools(&mut checklist);
+
+    println!();
+    if checklist.problems > 0 {
+        println!(
+            "{}Found {} {}",
+            FAILURE,
+            checklist.problems,
+            if checklist.problems == 1 {
+                "problem"
+            } else {
+                "problems"
+            }
+        );
+        process::exit(1);
+    } else {
+        println!("{}No problems found", OK);
+    }
+}
+
+fn check_config(
+    checklist: &mut Checklist,
+    config_path: &PathBuf,
+    proxy: Option<String>,
+) -> Option<Config> {
+    match toml::parse_file(config_path, proxy) {
+        Ok(config) => {
+            checklist.ok(format!("Configuration {} is valid", config_path.display()));
+            Some(config)
+        }
+        Err(e) => {
+            checklist.failure(
+                format!(
+                    "Configuration {} can't be used: {}",
+                    config_path.display(),
+                    e
+                ),
+                "Fix the configuration or print an example with 'tool default-config'",
+            );
+            None
+        }
+    }
+}
+
+fn check_tools(
+    checklist: &mut Checklist,
+    config: &Config,
+) {
+    if config.tools.is_empty() {
+        checklist.warning(
+            "No tools are configured",
+            "Add tools to the configuration, e.g. '[ripgrep]'",
+        );
+    }
+
+    for (tool_name, config_asset) in &config.tools {
+        match configure_tool(tool_name, config_asset) {
+            Tool::Known(tool_info) => checklist.ok(format!(
+                "{}: {}/{} ({})",
+                tool_name,
+                tool_info.owner,
+                tool_info.repo,
+                tool_info.tag.to_str_version()
+            )),
+            Tool::Error(e) => checklist.failure(
+                format!("{}: {}", tool_name, e),
+                "Specify 'owner', 'repo' and 'asset_name' for tools unknown to 'tool-sync'",
+            ),
+        }
+    }
+}
+
+fn check_token(checklist: &mut Checklist) {
+    match env::var("GITHUB_TOKEN") {
+        Ok(token) if !token.is_empty() => checklist.ok("GITHUB_TOKEN is set"),
+        _ => checklist.warning(
+            "GITHUB_TOKEN isn't set, GitHub allows only 60 requests per hour without it",
+            "Create a personal access token and export it as 'GITHUB_TOKEN':\n\n    \
+             https://docs.github.com/en/authentication/keeping-your-account-and-data-secure/creating-a-personal-access-token",
+        ),
+    }
+}
+
+/// Check that GitHub is reachable (through the proxy if it's configured) and
+/// the rate limit isn't exhausted
+fn check_github_api(
+    checklist: &mut Checklist,
+    proxy_address: Option<&str>,
+) {
+    let proxy = match proxy_address.map(ureq::Proxy::new) {
+        None => None,
+        Some(Ok(proxy)) => Some(proxy),
+        Some(Err(e)) => {
+            checklist.failure(
+                format!("Proxy address can't be parsed: {}", e),
+                "Use the format 'http://host:port' or 'socks5://host:port'",
+            );
+            return;
+        }
+    };
+
+    let through_proxy = match proxy_address {
+        Some(address) => format!(" through the proxy {}", address),
+        None => String::new(),
+    };
+
+    match fetch_rate_limit(proxy.as_ref()) {
+        Ok(status) if status.remaining == 0 => checklist.failure(
+            format!(
+                "GitHub API rate limit is exhausted until {}",
+                fmt_reset(status.reset)
+            ),
+            "Wait until the reset or use the '--wait-on-rate-limit' option",
+        ),
+        Ok(status) => checklist.ok(format!(
+            "GitHub API is reachable{}: {} of {} requests left, reset at {}",
+            through_proxy,
+            status.remaining,
+            status.limit,
+            fmt_reset(status.reset)
+        )),
+        Err(e) => match e.downcast_ref::<ureq::Error>() {
+            Some(ureq::Error::Status(401, _)) => checklist.failure(
+                "GitHub rejected the token from GITHUB_TOKEN",
+                "Check that the token is valid and hasn't expired",
+            ),
+            _ => checklist.failure(
+                format!("GitHub API isn't reachable{}: {}", through_proxy, e),
+                match proxy_address {
+                    Some(_) => "Check the network connection and that the proxy is running",
+                    None => "Check the network connection or configure a proxy with '--proxy'",
+                },
+            ),
+        },
+    }
+}
+
+/// All store directories from the config: the global one and per-tool ones
+fn store_directories(config: &Config) -> Vec<(String, Result<PathBuf, String>)> {
+    let global_directory = expand_directory(&config.store_directory).map_err(|e| e.to_string());
+
+    // relative per-tool directories are resolved even if the global one isn't
+    let base_directory = match &global_directory {
+        Ok(directory) => directory.clone(),
+        Err(_) => PathBuf::from(&config.store_directory),
+    };
+
+    let tools = config.tools.iter().filter_map(|(tool_name, config_asset)| {
+        let store_directory = config_asset.expand_store_directory(&base_directory)?;
+        Some((
+            format!("{}.store_directory", tool_name),
+            store_directory.map_err(|e| e.to_string()),
+        ))
+    });
+
+    std::iter::once((String::from("store_directory"), global_directory))
+        .chain(tools)
+        .collect()
+}
+
+fn check_store_directories(
+    checklist: &mut Checklist,
+    config: &Config,
+) {
+    let path_dirs = env_path::path_dirs();
+    let shell = env_path::current_shell();
+
+    for (key, store_directory) in store_directories(config) {
+        let directory = match store_directory {
+            Ok(directory) => directory,
+            Err(e) => {
+                checklist.failure(
+                    format!("'{}' can't be expanded: {}", key, e),
+                    "Check that all the environment variables in the path are set",
+                );
+                continue;
+            }
+        };
+
+        if !directory.is_dir() {
+            checklist.failure(
+                format!("'{}' ({}) doesn't exist", key, directory.display()),
+                "Create it or set 'create_store_directory = true' in the configuration",
+            );
+            continue;
+        }
+
+        match tempfile::tempfile_in(&directory) {
+            Ok(_) => checklist.ok(format!("'{}' ({}) is writable", key, directory.display())),
+            Err(e) => checklist.failure(
+                format!("'{}' ({}) isn't writable: {}", key, directory.display(), e),
+                "Check permissions of the directory",
+            ),
+        }
+
+        if env_path::is_on_path(&directory, &path_dirs) {
+            checklist.ok(format!(
+                "'{}' ({}) is listed in $PATH",
+                key,
+                directory.display()
+            ));
+        } else {
+            checklist.failure(
+                format!("'{}' ({}) isn't listed in $PATH", key, directory.display()),
+                &format!(
+                    "Add the following line to {}:\n\n    {}",
+                    env_path::shell_profile(shell.as_deref()),
+                    env_path::add_to_path_snippet(&directory, shell.as_deref())
+                ),
+            );
+        }
+    }
+}
+
+/// Check that previously installed executables run and aren't shadowed by
+/// other executables in $PATH
+fn check_installed_tools(checklist: &mut Checklist) {
+    let path_dirs = env_path::path_dirs();
+    let install_state = InstallState::load();
+
+    if install_state.tools.is_empty() {
+        checklist.ok("No tools were installed yet");
+    }
+
+    for (tool_name, installed) in install_state.tools {
+        for exe_path in installed.exe_paths() {
+            check_installed_executable(
+                checklist,
+                &tool_name,
+                &exe_path,
+                &installed.store_directory,
+                &path_dirs,
+            );
+        }
+    }
+}
+
+fn check_installed_executable(
+    checklist: &mut Checklist,
+    tool_name: &str,
+    exe_path: &Path,
+    store_directory: &Path,
+    path_dirs: &[PathBuf],
+) {
+    if !exe_path.is_file() {
+        checklist.failure(
+            format!("{}: {} is missing", tool_name, exe_path.display()),
+            &format!("Reinstall it with 'tool sync {}'", tool_name),
+        );
+        return;
+    }
+
+    match run_version(exe_path) {
+        Ok(version) => checklist.ok(format!("{}: {}", tool_name, version)),
+        Err(e) => checklist.warning(
+            format!(
+                "{}: '{} --version' failed: {}",
+                tool_name,
+                exe_path.display(),
+                e
+            ),
+            &format!(
+                "Some tools don't support '--version', otherwise reinstall it with 'tool sync {}'",
+                tool_name
+            ),
+        ),
+    }
+
+    match env_path::shadowed_by(exe_path, path_dirs) {
+        None => {}
+        Some(shadowing_path) => checklist.warning(
+            format!(
+                "{}: {} comes earlier in $PATH and runs instead of {}",
+                tool_name,
+                shadowing_path.display(),
+                exe_path.display()
+            ),
+            &shadowing_hint(&shadowing_path, store_directory),
+        ),
+    }
+}
+
+fn shadowing_hint(
+    shadowing_path: &Path,
+    store_directory: &Path,
+) -> String {
+    match shadowing_path.parent() {
+        Some(shadowing_directory) => format!(
+            "Put {} before {} in $PATH or remove {}",
+            store_directory.display(),
+            shadowing_directory.display(),
+            shadowing_path.display()
+        ),
+        None => format!("Remove {}", shadowing_path.display()),
+    }
+}
+
+/// Run the executable with '--version' and return the first line of output
+fn run_version(exe_path: &Path) -> Result<String, String> {
+    let mut child = Command::new(exe_path)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    // pipes are drained while waiting, otherwise a child with long output
+    // blocks on a full pipe and never exits
+    let stdout = child.stdout.take().map(read_in_thread);
+    let stderr = child.stderr.take().map(read_in_thread);
+
+    let start = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if start.elapsed() < VERSION_TIMEOUT => {
+                thread::sleep(Duration::from_millis(50))
+            }
+            Ok(None) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!(
+                    "no response after {} seconds",
+                    VERSION_TIMEOUT.as_secs()
+                ));
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    };
+
+    let stdout = stdout.map(join_output).unwrap_or_default();
+    let stderr = stderr.map(join_output).unwrap_or_default();
+    let stdout = String::from_utf8_lossy(&stdout);
+    let stderr = String::from_utf8_lossy(&stderr);
+
+    if status.success() {
+        Ok(first_line(&stdout)
+            .or_else(|| first_line(&stderr))
+            .unwrap_or("no version printed")
+            .to_owned())
+    } else {
+        Err(format!(
+            "{}{}",
+            status,
+            first_line(&stderr)
+                .map(|line| format!(": {}", line))
+                .unwrap_or_default()
+        ))
+    }
+}
+
+/// Read the whole pipe of a child process in a separate thread
+fn read_in_thread(mut pipe: impl Read + Send + 'static) -> JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut output = Vec::new();
+        let _ = pipe.read_to_end(&mut output);
+        output
+    })
+}
+
+fn join_output(reader: JoinHandle<Vec<u8>>) -> Vec<u8> {
+    reader.join().unwrap_or_default()
+}
+
+fn first_line(output: &str) -> Option<&str> {
+    output.lines().map(str::trim).find(|line| !line.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_non_empty_line() {
+        assert_eq!(
+            first_line("\nripgrep 13.0.0\n-SIMD -AVX\n"),
+            Some("ripgrep 13.0.0")
+        );
+        assert_eq!(first_line("  \n"), None);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn long_version_output_is_read() {
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let exe_path = tmp_dir.path().join("tool");
+
+        // more output than fits into a pipe buffer
+        fs::write(&exe_path, "#!/bin/sh\nyes 'tool 1.0' | head -n 50000\n").unwrap();
+        fs::set_permissions(&exe_path, fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert_eq!(run_version(&exe_path), Ok(String::from("tool 1.0")));
+    }
+
+    #[test]
+    fn hint_for_shadowing() {
+        assert_eq!(
+            shadowing_hint(Path::new("/usr/bin/rg"), Path::new("/home/user/bin")),
+            "Put /home/user/bin before /usr/bin in $PATH or remove /usr/bin/rg"
+        );
+    }
+}
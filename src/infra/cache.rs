@@ -0,0 +1,451 @@
+//! Local cache of data fetched from GitHub, stored in the 'tool-sync'
+//! directory inside the user cache directory (e.g. '~/.cache/tool-sync').
+
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::model::release::Asset;
+
+/// Root directory for all cached data
+pub fn cache_dir() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("tool-sync"))
+}
+
+/// Release metadata cached together with its 'ETag' to send conditional
+/// requests to GitHub
+#[derive(Debug, PartialEq, Eq)]
+pub struct CachedRelease {
+    pub etag: String,
+    pub body: String,
+}
+
+/// Cache of release JSON responses keyed by owner, repo and version
+pub struct ReleaseCache {
+    dir: PathBuf,
+}
+
+impl ReleaseCache {
+    /// Release cache in the default cache directory
+    pub fn open() -> Option<ReleaseCache> {
+        cache_dir().map(|dir| ReleaseCache::new(dir.join("releases")))
+    }
+
+    pub fn new(dir: PathBuf) -> ReleaseCache {
+        ReleaseCache { dir }
+    }
+
+    /// Paths to the cached release body and its 'ETag'
+    fn entry_paths(
+        &self,
+        owner: &str,
+        repo: &str,
+        version: &str,
+    ) -> (PathBuf, PathBuf) {
+        let dir = self.dir.join(sanitize(owner)).join(sanitize(repo));
+        let version = sanitize(version);
+
+        (
+            dir.join(format!("{}.json", version)),
+            dir.join(format!("{}.etag", version)),
+        )
+    }
+
+    pub fn load(
+        &self,
+        owner: &str,
+        repo: &str,
+        version: &str,
+    ) -> Option<CachedRelease> {
+        let (body_path, etag_path) = self.entry_paths(owner, repo, version);
+
+        let etag = fs::read_to_string(etag_path).ok()?;
+        let body = fs::read_to_string(body_path).ok()?;
+
+        Some(CachedRelease { etag, body })
+    }
+
+    pub fn store(
+        &self,
+        owner: &str,
+        repo: &str,
+        version: &str,
+        release: &CachedRelease,
+    ) -> std::io::Result<()> {
+        let (body_path, etag_path) = self.entry_paths(owner, repo, version);
+
+        if let Some(parent) = body_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // the old ETag must never be paired with the new body
+        if etag_path.exists() {
+            fs::remove_file(&etag_path)?;
+        }
+
+        fs::write(body_path, &release.body)?;
+        fs::write(etag_path, &release.etag)
+    }
+}
+
+/// Content-addressed cache of downloaded assets, so reinstalling the same
+/// version doesn't download it again.
+///
+/// Each asset is stored in its own directory named after the asset digest
+/// (or after its id and upload time if GitHub doesn't report the digest).
+/// The least recently used assets are removed when the total size of the
+/// cache exceeds `max_size`.
+pub struct DownloadCache {
+    dir: PathBuf,
+    max_size: u64,
+}
+
+/// Single asset stored in the download cache
+#[derive(Debug, PartialEq, Eq)]
+pub struct CacheEntry {
+    pub path: PathBuf,
+    pub size: u64,
+    pub last_used: SystemTime,
+}
+
+impl DownloadCache {
+    /// Download cache in the default cache directory
+    pub fn open(max_size: u64) -> Option<DownloadCache> {
+        cache_dir().map(|dir| DownloadCache::new(dir.join("downloads"), max_size))
+    }
+
+    pub fn new(
+        dir: PathBuf,
+        max_size: u64,
+    ) -> DownloadCache {
+        DownloadCache { dir, max_size }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn entry_path(
+        &self,
+        asset: &Asset,
+    ) -> PathBuf {
+        let key = match &asset.digest {
+            Some(digest) => sanitize(digest),
+            None => format!(
+                "asset-{}-{}",
+                asset.id,
+                sanitize(asset.updated_at.as_deref().unwrap_or_default())
+            ),
+        };
+
+        self.dir.join(key).join(sanitize(&asset.name))
+    }
+
+    /// Path to the cached asset if it's in the cache and has the expected
+    /// size and digest. A corrupted asset is removed from the cache.
+    pub fn get(
+        &self,
+        asset: &Asset,
+    ) -> Option<PathBuf> {
+        let path = self.entry_path(asset);
+        let metadata = fs::metadata(&path).ok()?;
+
+        if !metadata.is_file() || metadata.len() != asset.size {
+            return None;
+        }
+
+        if !matches_digest(&path, asset.digest.as_deref()).unwrap_or(false) {
+            if let Some(entry_dir) = path.parent() {
+                let _ = fs::remove_dir_all(entry_dir);
+            }
+            return None;
+        }
+
+        // mark the entry as recently used so it's evicted last
+        if let Ok(file) = File::options().append(true).open(&path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+
+        Some(path)
+    }
+
+    /// Copy the downloaded asset into the cache and evict old entries if the
+    /// cache is too big
+    pub fn put(
+        &self,
+        asset: &Asset,
+        downloaded_path: &Path,
+    ) -> io::Result<()> {
+        self.store(asset, downloaded_path, false)
+    }
+
+    /// Like `put` but moves the file into the cache instead of copying it.
+    /// The file must be on the same file system as the cache.
+    pub fn put_moved(
+        &self,
+        asset: &Asset,
+        downloaded_path: &Path,
+    ) -> io::Result<()> {
+        self.store(asset, downloaded_path, true)
+    }
+
+    fn store(
+        &self,
+        asset: &Asset,
+        downloaded_path: &Path,
+        is_moved: bool,
+    ) -> io::Result<()> {
+        if asset.size > self.max_size {
+            return Ok(());
+        }
+
+        if !matches_digest(downloaded_path, asset.digest.as_deref())? {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} doesn't match its digest", asset.name),
+            ));
+        }
+
+        let path = self.entry_path(asset);
+        let entry_dir = path.parent().unwrap_or(&self.dir);
+        fs::create_dir_all(entry_dir)?;
+
+        if is_moved {
+            fs::rename(downloaded_path, &path)?;
+        } else {
+            // copy into a temporary file first so an interrupted copy is never
+            // seen as a cached asset
+            let tmp_path = entry_dir.join(".download");
+            fs::copy(downloaded_path, &tmp_path)?;
+            fs::rename(&tmp_path, &path)?;
+        }
+
+        self.evict()
+    }
+
+    /// All cached assets from the most to the least recently used
+    pub fn entries(&self) -> io::Result<Vec<CacheEntry>> {
+        let mut entries = Vec::new();
+
+        let entry_dirs = match fs::read_dir(&self.dir) {
+            Ok(entry_dirs) => entry_dirs,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(entries),
+            Err(e) => return Err(e),
+        };
+
+        for entry_dir in entry_dirs {
+            let entry_dir = entry_dir?.path();
+            if !entry_dir.is_dir() {
+                continue;
+            }
+
+            for file in fs::read_dir(&entry_dir)? {
+                let path = file?.path();
+                let metadata = fs::metadata(&path)?;
+
+                let is_partial = path.file_name().is_some_and(|name| name == ".download");
+                if metadata.is_file() && !is_partial {
+                    entries.push(CacheEntry {
+                        path,
+                        size: metadata.len(),
+                        last_used: metadata.modified()?,
+                    });
+                }
+            }
+        }
+
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.last_used));
+        Ok(entries)
+    }
+
+    /// Remove the least recently used assets until the cache fits `max_size`
+    pub fn evict(&self) -> io::Result<()> {
+        let mut total_size = 0;
+
+        for entry in self.entries()? {
+            total_size += entry.size;
+
+            if total_size > self.max_size {
+                match entry.path.parent() {
+                    Some(entry_dir) => fs::remove_dir_all(entry_dir)?,
+                    None => fs::remove_file(&entry.path)?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Check the file against the sha256 digest reported by GitHub, e.g.
+/// "sha256:<hex>". Files without a digest or with a digest of another
+/// algorithm aren't checked.
+pub fn matches_digest(
+    path: &Path,
+    digest: Option<&str>,
+) -> io::Result<bool> {
+    let expected = match digest.and_then(|digest| digest.strip_prefix("sha256:")) {
+        Some(expected) => expected,
+        None => return Ok(true),
+    };
+
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    let actual: String = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    Ok(actual.eq_ignore_ascii_case(expected))
+}
+
+/// Make a single path component from a name, e.g. "tags/v1.0" -> "tags_v1.0"
+pub fn sanitize(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => c,
+            _ => '_',
+        })
+        .collect();
+
+    // don't allow special path components like '..'
+    sanitized.replace("..", "__")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitized_names() {
+        assert_eq!(sanitize("latest"), "latest");
+        assert_eq!(sanitize("tags/v1.0.0"), "tags_v1.0.0");
+        assert_eq!(sanitize("tags/../../etc"), "tags_______etc");
+    }
+
+    #[test]
+    fn release_cache_roundtrip() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let cache = ReleaseCache::new(tmp_dir.path().to_path_buf());
+
+        let release = CachedRelease {
+            etag: String::from(r#"W/"abcdef""#),
+            body: String::from(r#"{"tag_name": "v1.0.0", "assets": []}"#),
+        };
+
+        assert_eq!(cache.load("owner", "repo", "tags/v1.0.0"), None);
+
+        cache
+            .store("owner", "repo", "tags/v1.0.0", &release)
+            .unwrap();
+
+        assert_eq!(cache.load("owner", "repo", "tags/v1.0.0"), Some(release));
+        assert_eq!(cache.load("owner", "repo", "tags/v1.0.1"), None);
+        assert_eq!(cache.load("owner", "repo", "latest"), None);
+    }
+
+    /// sha256 digest of "1234"
+    const SHA256_1234: &str =
+        "sha256:03ac674216f3e15c761ee1a5e255f067953623c8b388b4459e13f978d7c846f4";
+
+    fn mk_asset(
+        id: u32,
+        digest: Option<&str>,
+    ) -> Asset {
+        Asset {
+            id,
+            name: format!("tool-{}.tar.gz", id),
+            size: 4,
+            updated_at: Some(String::from("2022-10-01T00:00:00Z")),
+            digest: digest.map(String::from),
+        }
+    }
+
+    #[test]
+    fn download_cache_roundtrip() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let cache = DownloadCache::new(tmp_dir.path().join("downloads"), 1024);
+
+        let downloaded_path = tmp_dir.path().join("tool.tar.gz");
+        fs::write(&downloaded_path, "1234").unwrap();
+
+        let asset = mk_asset(1, Some(SHA256_1234));
+        assert_eq!(cache.get(&asset), None);
+
+        cache.put(&asset, &downloaded_path).unwrap();
+
+        let cached_path = cache.get(&asset).unwrap();
+        assert_eq!(fs::read_to_string(cached_path).unwrap(), "1234");
+        assert_eq!(cache.get(&mk_asset(1, Some("sha256:012345"))), None);
+        assert_eq!(cache.get(&mk_asset(1, None)), None);
+    }
+
+    #[test]
+    fn download_cache_ignores_wrong_size() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let cache = DownloadCache::new(tmp_dir.path().join("downloads"), 1024);
+
+        let downloaded_path = tmp_dir.path().join("tool.tar.gz");
+        fs::write(&downloaded_path, "12").unwrap();
+
+        let asset = mk_asset(1, None);
+        cache.put(&asset, &downloaded_path).unwrap();
+
+        assert_eq!(cache.get(&asset), None);
+    }
+
+    #[test]
+    fn download_cache_evicts_least_recently_used() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let cache = DownloadCache::new(tmp_dir.path().join("downloads"), 8);
+
+        let downloaded_path = tmp_dir.path().join("tool.tar.gz");
+        fs::write(&downloaded_path, "1234").unwrap();
+
+        let (first, second, third) = (mk_asset(1, None), mk_asset(2, None), mk_asset(3, None));
+
+        cache.put(&first, &downloaded_path).unwrap();
+        cache.put(&second, &downloaded_path).unwrap();
+
+        // make sure the first entry looks older than the second one
+        let old = SystemTime::now() - std::time::Duration::from_secs(60);
+        File::options()
+            .append(true)
+            .open(cache.entry_path(&first))
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+
+        cache.put(&third, &downloaded_path).unwrap();
+
+        assert_eq!(cache.get(&first), None);
+        assert!(cache.get(&second).is_some());
+        assert!(cache.get(&third).is_some());
+    }
+
+    #[test]
+    fn download_cache_checks_digest() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let cache = DownloadCache::new(tmp_dir.path().join("downloads"), 1024);
+
+        let downloaded_path = tmp_dir.path().join("tool.tar.gz");
+        fs::write(&downloaded_path, "1235").unwrap();
+
+        // a corrupted download isn't cached
+        let asset = mk_asset(1, Some(SHA256_1234));
+        assert!(cache.put(&asset, &downloaded_path).is_err());
+        assert_eq!(cache.get(&asset), None);
+
+        // an asset corrupted in the cache is removed
+        fs::write(&downloaded_path, "1234").unwrap();
+        cache.put(&asset, &downloaded_path).unwrap();
+        fs::write(cache.entry_path(&asset), "1235").unwrap();
+
+        assert_eq!(cache.get(&asset), None);
+        assert!(!cache.entry_path(&asset).exists());
+    }
+}
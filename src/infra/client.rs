@@ -1,20 +1,72 @@
+use serde::Deserialize;
 use std::env;
 use std::error::Error;
 use std::io::Read;
+use std::rc::Rc;
 
+use crate::infra::cache::{CachedRelease, ReleaseCache};
+use crate::infra::rate_limit::RateLimit;
+use crate::infra::retry::RetryPolicy;
 use crate::model::release::{Asset, Release};
 
+/// How many releases to request per page (maximum allowed by GitHub)
+const RELEASES_PER_PAGE: u8 = 100;
+
+/// Key of the first page of releases in the release cache. Other keys are
+/// versions like "latest" or "tags/v1.0.0", so they never clash.
+const RELEASES_CACHE_KEY: &str = "releases";
+
+/// Body of a GitHub response or of the cached one if it didn't change
+struct CachedResponse {
+    body: String,
+
+    /// The 'Link' header with pagination, `None` for a cached body
+    link: Option<String>,
+
+    is_cached: bool,
+}
+
 /// GitHub API client to handle all API requests
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Client {
     pub owner: String,
     pub repo: String,
     pub version: String,
 
     pub proxy: Option<ureq::Proxy>,
+
+    pub retry: RetryPolicy,
+
+    pub rate_limit: Rc<RateLimit>,
+}
+
+/// Stream of asset bytes starting from `offset`
+pub struct AssetStream {
+    pub reader: Box<dyn Read + Send + Sync>,
+
+    /// Position in the asset where the stream starts. This can be `0` even
+    /// when a later position was requested if the server doesn't support
+    /// resuming downloads.
+    pub offset: u64,
 }
 
 impl Client {
+    /// Client for assets installed from a local copy, it never sends requests
+    pub fn offline(
+        owner: String,
+        repo: String,
+        tag: &str,
+    ) -> Client {
+        Client {
+            owner,
+            repo,
+            version: format!("tags/{}", tag),
+            proxy: None,
+            retry: RetryPolicy::default(),
+            rate_limit: Rc::new(RateLimit::new(false)),
+        }
+    }
+
     fn release_url(&self) -> String {
         format!(
             "https://api.github.com/repos/{owner}/{repo}/releases/{version}",
@@ -36,59 +88,272 @@ impl Client {
         )
     }
 
-    pub fn fetch_release_info(&self) -> Result<Release, Box<dyn Error>> {
-        let release_url = self.release_url();
+    fn releases_url(&self) -> String {
+        format!(
+            "https://api.github.com/repos/{owner}/{repo}/releases?per_page={per_page}",
+            owner = self.owner,
+            repo = self.repo,
+            per_page = RELEASES_PER_PAGE,
+        )
+    }
+
+    fn mk_request(
+        &self,
+        url: &str,
+        accept: &str,
+    ) -> ureq::Request {
+        mk_request(self.proxy.as_ref(), url, accept)
+    }
 
-        let req = match &self.proxy {
-            Some(proxy) => {
-                let agent = ureq::AgentBuilder::new().proxy(proxy.clone()).build();
+    /// Send a request unless the rate limit is exhausted. Transient errors
+    /// are retried according to the retry policy.
+    fn call(
+        &self,
+        req: &ureq::Request,
+    ) -> Result<ureq::Response, Box<dyn Error>> {
+        loop {
+            self.rate_limit.acquire()?;
 
-                add_auth_header(
-                    agent
-                        .get(&release_url)
-                        .set("Accept", "application/vnd.github+json")
-                        .set("User-Agent", "chshersh/tool-sync-0.2.0"),
-                )
+            match self.retry.call(req) {
+                Ok(response) => {
+                    self.rate_limit.update(&response);
+                    return Ok(response);
+                }
+                Err(e) => {
+                    if let ureq::Error::Status(403 | 429, response) = e.as_ref() {
+                        self.rate_limit.update(response);
+
+                        // wait for the reset or fail on the next iteration
+                        if self.rate_limit.exhausted_until().is_some() {
+                            continue;
+                        }
+                    }
+
+                    return Err(e);
+                }
             }
-            None => add_auth_header(
-                ureq::get(&release_url)
-                    .set("Accept", "application/vnd.github+json")
-                    .set("User-Agent", "chshersh/tool-sync-0.2.0"),
-            ),
+        }
+    }
+
+    /// Send a conditional request with the 'ETag' of the response cached
+    /// under 'cache_key' and cache the new response
+    fn call_cached(
+        &self,
+        url: &str,
+        cache_key: &str,
+    ) -> Result<CachedResponse, Box<dyn Error>> {
+        let req = self.mk_request(url, "application/vnd.github+json");
+
+        let release_cache = ReleaseCache::open();
+        let cached = release_cache
+            .as_ref()
+            .and_then(|cache| cache.load(&self.owner, &self.repo, cache_key));
+
+        // GitHub doesn't count '304 Not Modified' responses against the rate limit
+        let req = match &cached {
+            Some(cached) => req.set("If-None-Match", &cached.etag),
+            None => req,
         };
 
-        let release: Release = req.call()?.into_json()?;
+        let response = self.call(&req)?;
 
+        if let (304, Some(cached)) = (response.status(), cached) {
+            return Ok(CachedResponse {
+                body: cached.body,
+                link: None,
+                is_cached: true,
+            });
+        }
+
+        let etag = response.header("ETag").map(String::from);
+        let link = response.header("Link").map(String::from);
+        let body = response.into_string()?;
+
+        if let (Some(cache), Some(etag)) = (release_cache, etag) {
+            // the cache is only an optimisation so failing to write it is fine
+            let _ = cache.store(
+                &self.owner,
+                &self.repo,
+                cache_key,
+                &CachedRelease {
+                    etag,
+                    body: body.clone(),
+                },
+            );
+        }
+
+        Ok(CachedResponse {
+            body,
+            link,
+            is_cached: false,
+        })
+    }
+
+    pub fn fetch_release_info(&self) -> Result<Release, Box<dyn Error>> {
+        let response = self.call_cached(&self.release_url(), &self.version)?;
+        let release: Release = ureq::serde_json::from_str(&response.body)?;
         Ok(release)
     }
 
+    /// Fetch releases from newest to oldest, following the pagination
+    /// links from GitHub but requesting at most `max_pages` pages
+    pub fn list_releases(
+        &self,
+        max_pages: usize,
+    ) -> Result<Vec<Release>, Box<dyn Error>> {
+        let mut releases: Vec<Release> = Vec::new();
+        let mut next_url = Some(self.releases_url());
+        let mut fetched_pages = 0;
+
+        while let Some(url) = next_url {
+            if fetched_pages == max_pages {
+                break;
+            }
+
+            // only the first page is cached, it's the only one needed usually
+            let page: Vec<Release> = if fetched_pages == 0 {
+                let response = self.call_cached(&url, RELEASES_CACHE_KEY)?;
+                let page: Vec<Release> = ureq::serde_json::from_str(&response.body)?;
+
+                next_url = if !response.is_cached {
+                    response.link.as_deref().and_then(next_page_url)
+                } else if page.len() == usize::from(RELEASES_PER_PAGE) {
+                    Some(format!("{}&page=2", self.releases_url()))
+                } else {
+                    None
+                };
+                page
+            } else {
+                let req = self.mk_request(&url, "application/vnd.github+json");
+                let response = self.call(&req)?;
+
+                next_url = response.header("Link").and_then(next_page_url);
+                response.into_json()?
+            };
+
+            fetched_pages += 1;
+            releases.extend(page);
+        }
+
+        Ok(releases)
+    }
+
+    /// Fetch the most recent published release, prereleases included.
+    ///
+    /// GitHub returns releases sorted from newest to oldest; drafts are
+    /// skipped because they don't have a published tag yet.
+    pub fn fetch_newest_release(&self) -> Result<Release, Box<dyn Error>> {
+        self.list_releases(1)?
+            .into_iter()
+            .find(|release| !release.draft)
+            .ok_or_else(|| {
+                format!(
+                    "The {owner}/{repo} doesn't have any published releases.",
+                    owner = self.owner,
+                    repo = self.repo
+                )
+                .into()
+            })
+    }
+
+    /// Start downloading an asset from the given `offset` (to resume an
+    /// interrupted download) or from the beginning if `offset` is `0`
     pub fn get_asset_stream(
         &self,
         asset: &Asset,
-    ) -> Result<Box<dyn Read + Send + Sync>, Box<ureq::Error>> {
+        offset: u64,
+    ) -> Result<AssetStream, Box<dyn Error>> {
         let asset_url = self.asset_url(asset.id);
-        let req = match &self.proxy {
-            Some(proxy) => {
-                let agent = ureq::AgentBuilder::new().proxy(proxy.clone()).build();
-
-                add_auth_header(
-                    agent
-                        .get(&asset_url)
-                        .set("Accept", "application/octet-stream")
-                        .set("User-Agent", "chshersh/tool-sync-0.2.0"),
-                )
-            }
-            None => add_auth_header(
-                ureq::get(&asset_url)
-                    .set("Accept", "application/octet-stream")
-                    .set("User-Agent", "chshersh/tool-sync-0.2.0"),
-            ),
-        };
+        let mut req = self.mk_request(&asset_url, "application/octet-stream");
+
+        if offset > 0 {
+            req = req.set("Range", &format!("bytes={}-", offset));
+        }
+
+        let response = self.call(&req)?;
+
+        // 206 Partial Content means the server honoured the 'Range' header
+        let offset = if response.status() == 206 { offset } else { 0 };
 
-        Ok(req.call()?.into_reader())
+        Ok(AssetStream {
+            reader: response.into_reader(),
+            offset,
+        })
     }
 }
 
+/// Extract the URL of the next page from the GitHub 'Link' header, e.g.
+///
+/// ```text
+/// <https://api.github.com/repositories/1/releases?page=2>; rel="next", <...>; rel="last"
+/// ```
+fn next_page_url(link_header: &str) -> Option<String> {
+    link_header.split(',').find_map(|link| {
+        let (url, params) = link.split_once(';')?;
+        let is_next = params
+            .split(';')
+            .any(|param| param.trim() == r#"rel="next""#);
+
+        if is_next {
+            Some(
+                url.trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_owned(),
+            )
+        } else {
+            None
+        }
+    })
+}
+
+/// Status of the GitHub API rate limit for the current token
+#[derive(Debug, PartialEq, Eq, Deserialize)]
+pub struct RateLimitStatus {
+    pub limit: u64,
+    pub remaining: u64,
+
+    /// Time when the rate limit resets (in UTC epoch seconds)
+    pub reset: u64,
+}
+
+#[derive(Deserialize)]
+struct RateLimitResponse {
+    rate: RateLimitStatus,
+}
+
+/// Fetch the rate limit status. GitHub doesn't count this request against
+/// the rate limit.
+pub fn fetch_rate_limit(proxy: Option<&ureq::Proxy>) -> Result<RateLimitStatus, Box<dyn Error>> {
+    let req = mk_request(
+        proxy,
+        "https://api.github.com/rate_limit",
+        "application/vnd.github+json",
+    );
+
+    let response: RateLimitResponse = req.call()?.into_json()?;
+    Ok(response.rate)
+}
+
+fn mk_request(
+    proxy: Option<&ureq::Proxy>,
+    url: &str,
+    accept: &str,
+) -> ureq::Request {
+    let req = match proxy {
+        Some(proxy) => {
+            let agent = ureq::AgentBuilder::new().proxy(proxy.clone()).build();
+            agent.get(url)
+        }
+        None => ureq::get(url),
+    };
+
+    add_auth_header(
+        req.set("Accept", accept)
+            .set("User-Agent", "chshersh/tool-sync-0.2.0"),
+    )
+}
+
 fn add_auth_header(req: ureq::Request) -> ureq::Request {
     match env::var("GITHUB_TOKEN") {
         Err(_) => req,
@@ -109,6 +374,8 @@ mod tests {
             repo: String::from("REPO"),
             version: ToolInfoTag::Latest.to_str_version(),
             proxy: None,
+            retry: RetryPolicy::default(),
+            rate_limit: Rc::new(RateLimit::new(false)),
         };
 
         assert_eq!(
@@ -124,6 +391,8 @@ mod tests {
             repo: String::from("REPO"),
             version: ToolInfoTag::Specific(String::from("SPECIFIC_TAG")).to_str_version(),
             proxy: None,
+            retry: RetryPolicy::default(),
+            rate_limit: Rc::new(RateLimit::new(false)),
         };
 
         assert_eq!(
@@ -131,4 +400,59 @@ mod tests {
             "https://api.github.com/repos/OWNER/REPO/releases/tags/SPECIFIC_TAG"
         );
     }
+
+    #[test]
+    fn releases_url_is_correct() {
+        let client = Client {
+            owner: String::from("OWNER"),
+            repo: String::from("REPO"),
+            version: ToolInfoTag::Prerelease.to_str_version(),
+            proxy: None,
+            retry: RetryPolicy::default(),
+            rate_limit: Rc::new(RateLimit::new(false)),
+        };
+
+        assert_eq!(
+            client.releases_url(),
+            "https://api.github.com/repos/OWNER/REPO/releases?per_page=100"
+        );
+    }
+
+    #[test]
+    fn next_page_url_is_found() {
+        let link_header = r#"<https://api.github.com/repositories/1/releases?per_page=100&page=2>; rel="next", <https://api.github.com/repositories/1/releases?per_page=100&page=5>; rel="last""#;
+
+        assert_eq!(
+            next_page_url(link_header),
+            Some(String::from(
+                "https://api.github.com/repositories/1/releases?per_page=100&page=2"
+            ))
+        );
+    }
+
+    #[test]
+    fn next_page_url_on_last_page() {
+        let link_header = r#"<https://api.github.com/repositories/1/releases?per_page=100&page=1>; rel="first", <https://api.github.com/repositories/1/releases?per_page=100&page=4>; rel="prev""#;
+
+        assert_eq!(next_page_url(link_header), None);
+    }
+
+    #[test]
+    fn rate_limit_status_from_json() {
+        let json = r#"{
+            "resources": {"core": {"limit": 60, "remaining": 59, "reset": 1666000000, "used": 1}},
+            "rate": {"limit": 60, "remaining": 59, "reset": 1666000000, "used": 1}
+        }"#;
+
+        let response: RateLimitResponse = ureq::serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            response.rate,
+            RateLimitStatus {
+                limit: 60,
+                remaining: 59,
+                reset: 1666000000,
+            }
+        );
+    }
 }
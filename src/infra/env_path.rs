@@ -0,0 +1,213 @@
+//! Helpers for inspecting the 'PATH' environment variable.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directories listed in the 'PATH' environment variable
+pub fn path_dirs() -> Vec<PathBuf> {
+    env::var_os("PATH")
+        .map(|path| env::split_paths(&path).collect())
+        .unwrap_or_default()
+}
+
+/// Whether 'dir' is one of 'path_dirs', ignoring symlinks and trailing slashes
+pub fn is_on_path(
+    dir: &Path,
+    path_dirs: &[PathBuf],
+) -> bool {
+    let dir = normalize(dir);
+    path_dirs.iter().any(|path_dir| normalize(path_dir) == dir)
+}
+
+/// First executable called 'exe_name' in 'path_dirs', i.e. the one run by name
+pub fn find_executable(
+    exe_name: &str,
+    path_dirs: &[PathBuf],
+) -> Option<PathBuf> {
+    path_dirs
+        .iter()
+        .find_map(|path_dir| executable_in(path_dir, exe_name))
+}
+
+/// The file run as 'exe_name' from 'dir' if it has execute permissions
+#[cfg(target_family = "unix")]
+fn executable_in(
+    dir: &Path,
+    exe_name: &str,
+) -> Option<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let exe_path = dir.join(exe_name);
+    let metadata = fs::metadata(&exe_path).ok()?;
+    (metadata.is_file() && metadata.permissions().mode() & 0o111 != 0).then_some(exe_path)
+}
+
+/// The file run as 'exe_name' from 'dir', trying every extension from
+/// 'PATHEXT' like the shell does for a command typed without one
+#[cfg(target_family = "windows")]
+fn executable_in(
+    dir: &Path,
+    exe_name: &str,
+) -> Option<PathBuf> {
+    let command = exe_name.strip_suffix(".exe").unwrap_or(exe_name);
+    let path_ext = env::var("PATHEXT").unwrap_or_else(|_| String::from(".COM;.EXE;.BAT;.CMD"));
+
+    path_ext
+        .split(';')
+        .filter(|ext| !ext.is_empty())
+        .map(|ext| dir.join(format!("{}{}", command, ext)))
+        .find(|exe_path| exe_path.is_file())
+}
+
+/// Another executable with the same name found earlier in 'path_dirs' that
+/// runs instead of 'exe_path'
+pub fn shadowed_by(
+    exe_path: &Path,
+    path_dirs: &[PathBuf],
+) -> Option<PathBuf> {
+    let exe_name = exe_path.file_name()?.to_str()?;
+    let found = find_executable(exe_name, path_dirs)?;
+
+    if normalize(&found) == normalize(exe_path) {
+        None
+    } else {
+        Some(found)
+    }
+}
+
+fn normalize(dir: &Path) -> PathBuf {
+    fs::canonicalize(dir).unwrap_or_else(|_| dir.components().collect())
+}
+
+/// Name of the current user shell, e.g. "zsh"
+pub fn current_shell() -> Option<String> {
+    let shell = env::var_os("SHELL")?;
+    let name = Path::new(&shell).file_name()?;
+    Some(name.to_string_lossy().into_owned())
+}
+
+/// Shell configuration file where 'PATH' is usually changed
+pub fn shell_profile(shell: Option<&str>) -> &'static str {
+    match shell {
+        Some("zsh") => "~/.zshrc",
+        Some("bash") => "~/.bashrc",
+        Some("fish") => "~/.config/fish/config.fish",
+        Some("pwsh" | "powershell") => "your PowerShell profile ($PROFILE)",
+        _ if cfg!(windows) => "your PowerShell profile ($PROFILE)",
+        _ => "your shell profile (e.g. ~/.profile)",
+    }
+}
+
+/// Shell line that adds 'dir' to 'PATH'
+pub fn add_to_path_snippet(
+    dir: &Path,
+    shell: Option<&str>,
+) -> String {
+    let dir = dir.display();
+
+    match shell {
+        Some("fish") => format!("fish_add_path {}", dir),
+        Some("pwsh" | "powershell") => format!(r#"$env:Path = "{};$env:Path""#, dir),
+        None if cfg!(windows) => format!(r#"$env:Path = "{};$env:Path""#, dir),
+        _ => format!(r#"export PATH="{}:$PATH""#, dir),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::model::asset_name::mk_exe_name;
+
+    #[test]
+    fn dir_on_path() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let bin = tmp_dir.path().join("bin");
+        fs::create_dir(&bin).unwrap();
+
+        let path_dirs = vec![PathBuf::from("/usr/bin"), bin.clone()];
+
+        assert!(is_on_path(&bin, &path_dirs));
+        assert!(is_on_path(&tmp_dir.path().join("bin/"), &path_dirs));
+        assert!(!is_on_path(tmp_dir.path(), &path_dirs));
+    }
+
+    #[test]
+    fn missing_dir_on_path() {
+        let path_dirs = vec![PathBuf::from("/does/not/exist/")];
+
+        assert!(is_on_path(Path::new("/does/not/exist"), &path_dirs));
+        assert!(!is_on_path(Path::new("/does/not"), &path_dirs));
+    }
+
+    fn write_executable(path: &Path) {
+        fs::write(path, "").unwrap();
+
+        #[cfg(target_family = "unix")]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    }
+
+    #[test]
+    fn shadowed_executable() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let (system, store) = (tmp_dir.path().join("system"), tmp_dir.path().join("store"));
+        fs::create_dir(&system).unwrap();
+        fs::create_dir(&store).unwrap();
+
+        let installed = store.join(mk_exe_name("rg"));
+        write_executable(&installed);
+
+        assert_eq!(
+            shadowed_by(&installed, &[system.clone(), store.clone()]),
+            None
+        );
+        assert_eq!(shadowed_by(&installed, std::slice::from_ref(&store)), None);
+        assert_eq!(shadowed_by(&installed, &[]), None);
+
+        write_executable(&system.join(mk_exe_name("rg")));
+
+        assert_eq!(
+            shadowed_by(&installed, &[system.clone(), store.clone()]),
+            Some(system.join(mk_exe_name("rg")))
+        );
+        assert_eq!(shadowed_by(&installed, &[store, system]), None);
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn non_executable_file_does_not_shadow() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let (system, store) = (tmp_dir.path().join("system"), tmp_dir.path().join("store"));
+        fs::create_dir(&system).unwrap();
+        fs::create_dir(&store).unwrap();
+
+        let installed = store.join("rg");
+        write_executable(&installed);
+        fs::write(system.join("rg"), "").unwrap();
+
+        assert_eq!(shadowed_by(&installed, &[system, store]), None);
+    }
+
+    #[test]
+    fn path_snippets() {
+        let dir = Path::new("/home/user/bin");
+
+        assert_eq!(
+            add_to_path_snippet(dir, Some("bash")),
+            r#"export PATH="/home/user/bin:$PATH""#
+        );
+        assert_eq!(
+            add_to_path_snippet(dir, Some("fish")),
+            "fish_add_path /home/user/bin"
+        );
+        assert_eq!(
+            add_to_path_snippet(dir, Some("pwsh")),
+            r#"$env:Path = "/home/user/bin;$env:Path""#
+        );
+        assert_eq!(shell_profile(Some("zsh")), "~/.zshrc");
+    }
+}
@@ -1,2 +1,6 @@
+pub mod cache;
 pub mod client;
+pub mod env_path;
 pub mod err;
+pub mod rate_limit;
+pub mod retry;
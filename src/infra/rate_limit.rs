@@ -0,0 +1,203 @@
+use std::cell::Cell;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Tracks the GitHub API rate limit using the 'X-RateLimit-*' headers of
+/// responses, so that no more requests are sent once the limit is exhausted.
+///
+/// A single value is shared between all clients during one run.
+#[derive(Debug)]
+pub struct RateLimit {
+    /// Sleep until the limit is reset instead of failing
+    wait: bool,
+
+    /// Requests left in the current rate limit window
+    remaining: Cell<Option<u64>>,
+
+    /// Time when the current window resets (in UTC epoch seconds)
+    reset: Cell<Option<u64>>,
+}
+
+/// Error returned instead of sending a request when the rate limit is exhausted
+#[derive(Debug, PartialEq, Eq)]
+pub struct RateLimitExceeded {
+    /// Time when the rate limit resets (in UTC epoch seconds)
+    pub reset: u64,
+}
+
+impl Display for RateLimitExceeded {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        write!(
+            f,
+            r#"GitHub API rate limit exceeded. The limit resets at {reset}.
+
+Export a personal access token as the 'GITHUB_TOKEN' environment variable to
+get a higher limit or use the '--wait-on-rate-limit' option to wait until
+the reset:
+
+    * https://docs.github.com/en/authentication/keeping-your-account-and-data-secure/creating-a-personal-access-token"#,
+            reset = fmt_reset(self.reset),
+        )
+    }
+}
+
+impl Error for RateLimitExceeded {}
+
+impl RateLimit {
+    pub fn new(wait: bool) -> RateLimit {
+        RateLimit {
+            wait,
+            remaining: Cell::new(None),
+            reset: Cell::new(None),
+        }
+    }
+
+    /// Remember the rate limit status from the response headers
+    pub fn update(
+        &self,
+        response: &ureq::Response,
+    ) {
+        let header_value = |name| response.header(name).and_then(|v| v.parse::<u64>().ok());
+
+        if let Some(remaining) = header_value("X-RateLimit-Remaining") {
+            self.remaining.set(Some(remaining));
+        }
+
+        if let Some(reset) = header_value("X-RateLimit-Reset") {
+            self.reset.set(Some(reset));
+        }
+    }
+
+    /// Returns the reset time if no requests are left until then
+    pub fn exhausted_until(&self) -> Option<u64> {
+        match (self.remaining.get(), self.reset.get()) {
+            (Some(0), Some(reset)) if reset > now() => Some(reset),
+            _ => None,
+        }
+    }
+
+    /// Check whether a request can be sent. Sleeps until the reset if the
+    /// limit is exhausted and waiting is enabled, fails otherwise.
+    pub fn acquire(&self) -> Result<(), RateLimitExceeded> {
+        match self.exhausted_until() {
+            None => Ok(()),
+            Some(reset) if self.wait => {
+                eprintln!(
+                    "GitHub API rate limit exceeded. Waiting until {}...",
+                    fmt_reset(reset)
+                );
+
+                // wait one more second to be sure the limit is reset
+                thread::sleep(Duration::from_secs(reset.saturating_sub(now()) + 1));
+                self.remaining.set(None);
+                Ok(())
+            }
+            Some(reset) => Err(RateLimitExceeded { reset }),
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Format the reset time relative to now, like "14:05:12 UTC (in 12 minutes)"
+pub fn fmt_reset(reset: u64) -> String {
+    fmt_reset_time(reset, now())
+}
+
+/// Format the reset time like "14:05:12 UTC (in 12 minutes)"
+fn fmt_reset_time(
+    reset: u64,
+    now: u64,
+) -> String {
+    let seconds_of_day = reset % 86400;
+    let minutes_left = reset.saturating_sub(now).div_ceil(60);
+
+    format!(
+        "{:02}:{:02}:{:02} UTC (in {} {})",
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60,
+        minutes_left,
+        if minutes_left == 1 {
+            "minute"
+        } else {
+            "minutes"
+        }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mk_response(
+        remaining: u64,
+        reset: u64,
+    ) -> ureq::Response {
+        format!(
+            "HTTP/1.1 200 OK\r\nX-RateLimit-Remaining: {}\r\nX-RateLimit-Reset: {}\r\n\r\n",
+            remaining, reset
+        )
+        .parse()
+        .unwrap()
+    }
+
+    #[test]
+    fn unknown_limit_is_not_exhausted() {
+        let rate_limit = RateLimit::new(false);
+
+        assert_eq!(rate_limit.exhausted_until(), None);
+        assert_eq!(rate_limit.acquire(), Ok(()));
+    }
+
+    #[test]
+    fn remaining_requests_are_not_exhausted() {
+        let rate_limit = RateLimit::new(false);
+        rate_limit.update(&mk_response(10, now() + 600));
+
+        assert_eq!(rate_limit.acquire(), Ok(()));
+    }
+
+    #[test]
+    fn no_remaining_requests_are_exhausted() {
+        let reset = now() + 600;
+        let rate_limit = RateLimit::new(false);
+        rate_limit.update(&mk_response(0, reset));
+
+        assert_eq!(rate_limit.exhausted_until(), Some(reset));
+        assert_eq!(rate_limit.acquire(), Err(RateLimitExceeded { reset }));
+    }
+
+    #[test]
+    fn passed_reset_is_not_exhausted() {
+        let rate_limit = RateLimit::new(false);
+        rate_limit.update(&mk_response(0, now() - 1));
+
+        assert_eq!(rate_limit.acquire(), Ok(()));
+    }
+
+    #[test]
+    fn reset_time_format() {
+        // 1970-01-02 14:05:12 UTC
+        let reset = 86400 + 14 * 3600 + 5 * 60 + 12;
+
+        assert_eq!(
+            fmt_reset_time(reset, reset - 12 * 60),
+            "14:05:12 UTC (in 12 minutes)"
+        );
+        assert_eq!(
+            fmt_reset_time(reset, reset - 30),
+            "14:05:12 UTC (in 1 minute)"
+        );
+    }
+}
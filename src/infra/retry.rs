@@ -0,0 +1,143 @@
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use ureq::ErrorKind;
+
+/// Default number of retries for a failed request
+pub const DEFAULT_RETRIES: u32 = 3;
+
+const INITIAL_DELAY: Duration = Duration::from_millis(500);
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// How to retry requests that failed because of transient network problems
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// How many times to retry a failed request
+    pub max_retries: u32,
+
+    /// Delay before the first retry; doubled on each subsequent retry
+    pub initial_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::with_retries(DEFAULT_RETRIES)
+    }
+}
+
+impl RetryPolicy {
+    pub fn with_retries(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            initial_delay: INITIAL_DELAY,
+        }
+    }
+
+    /// Delay before the given retry (starting from 1) without jitter
+    fn backoff(
+        &self,
+        retry: u32,
+    ) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_delay.saturating_mul(factor).min(MAX_DELAY)
+    }
+
+    /// Sleep before the given retry (starting from 1). Adds up to 50% of
+    /// random jitter so that parallel clients don't retry at the same time.
+    pub fn wait(
+        &self,
+        retry: u32,
+    ) {
+        let backoff = self.backoff(retry);
+        thread::sleep(backoff + jitter(backoff / 2));
+    }
+
+    /// Send a request, retrying on 5xx responses and connection errors
+    pub fn call(
+        &self,
+        req: &ureq::Request,
+    ) -> Result<ureq::Response, Box<ureq::Error>> {
+        let mut retry = 0;
+
+        loop {
+            match req.clone().call() {
+                Ok(response) => return Ok(response),
+                Err(e) if retry < self.max_retries && is_transient(&e) => {
+                    retry += 1;
+                    self.wait(retry);
+                }
+                Err(e) => return Err(Box::new(e)),
+            }
+        }
+    }
+}
+
+/// Whether the request may succeed if retried
+pub fn is_transient(err: &ureq::Error) -> bool {
+    match err {
+        ureq::Error::Status(status, _) => *status >= 500,
+        ureq::Error::Transport(transport) => matches!(
+            transport.kind(),
+            ErrorKind::Dns
+                | ErrorKind::ConnectionFailed
+                | ErrorKind::Io
+                | ErrorKind::BadStatus
+                | ErrorKind::ProxyConnect
+        ),
+    }
+}
+
+/// Pseudo-random duration in the range `[0, max)`. Good enough for spreading
+/// retries and doesn't require a dependency on a random number generator.
+fn jitter(max: Duration) -> Duration {
+    let max_millis = max.as_millis() as u64;
+    if max_millis == 0 {
+        return Duration::ZERO;
+    }
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or_default();
+
+    Duration::from_millis(nanos % max_millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_is_exponential() {
+        let policy = RetryPolicy::default();
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(500));
+        assert_eq!(policy.backoff(2), Duration::from_secs(1));
+        assert_eq!(policy.backoff(3), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let policy = RetryPolicy::default();
+
+        assert_eq!(policy.backoff(10), MAX_DELAY);
+        assert_eq!(policy.backoff(100), MAX_DELAY);
+    }
+
+    #[test]
+    fn jitter_is_bounded() {
+        let max = Duration::from_millis(250);
+
+        assert!(jitter(max) < max);
+        assert_eq!(jitter(Duration::ZERO), Duration::ZERO);
+    }
+
+    #[test]
+    fn server_errors_are_transient() {
+        let response = ureq::Response::new(503, "Service Unavailable", "").unwrap();
+        assert!(is_transient(&ureq::Error::Status(503, response)));
+
+        let response = ureq::Response::new(404, "Not Found", "").unwrap();
+        assert!(!is_transient(&ureq::Error::Status(404, response)));
+    }
+}
@@ -11,8 +11,16 @@ pub fn install(
     config_path: PathBuf,
     name: String,
     proxy: Option<String>,
+    wait_on_rate_limit: bool,
+    create_dirs: bool,
 ) {
-    toml::with_parsed_file(config_path, proxy, |config| install_tool(config, name))
+    toml::with_parsed_file(
+        config_path,
+        proxy,
+        wait_on_rate_limit,
+        create_dirs,
+        |config| install_tool(config, name),
+    )
 }
 
 /// Find if the tool is already mentioned in the config
@@ -21,7 +29,7 @@ fn install_tool(
     name: String,
 ) {
     if let Some(tool_info) = lookup_tool(&name) {
-        sync::sync_single_tool(config, name, tool_info.into());
+        sync::sync_single_tool(config, name, tool_info.into(), false);
     } else {
         let tools = fmt_tool_names_info(|name| format!("    * {name}"));
 
@@ -1,8 +1,11 @@
+mod cache;
 mod completion;
 mod config;
+mod doctor;
 mod infra;
 mod install;
 mod model;
+mod releases;
 mod sync;
 
 use clap::{CommandFactory, Parser};
@@ -31,8 +34,45 @@ pub fn run() {
             true => print_default_path(),
             false => config::template::generate_default_config(),
         },
-        Command::Sync { tool } => sync::sync_from_path(config_path, tool, cli.proxy),
-        Command::Install { name } => install::install(config_path, name, cli.proxy),
+        Command::Sync { tool, offline } => sync::sync_from_path(
+            config_path,
+            tool,
+            offline,
+            cli.proxy,
+            cli.wait_on_rate_limit,
+            cli.create_dirs,
+        ),
+        Command::Install { name } => install::install(
+            config_path,
+            name,
+            cli.proxy,
+            cli.wait_on_rate_limit,
+            cli.create_dirs,
+        ),
+        Command::Releases { name, limit, notes } => releases::releases(
+            config_path,
+            name,
+            limit,
+            notes,
+            cli.proxy,
+            cli.wait_on_rate_limit,
+        ),
+        Command::Bundle { output, oses } => sync::bundle::bundle_from_path(
+            config_path,
+            output,
+            oses,
+            cli.proxy,
+            cli.wait_on_rate_limit,
+        ),
+        Command::ImportBundle { bundle } => {
+            sync::bundle::import_from_path(config_path, bundle, cli.create_dirs)
+        }
+        Command::Rollback { name, pin } => {
+            sync::rollback::rollback_from_path(config_path, name, pin)
+        }
+        Command::Uninstall { name } => sync::uninstall::uninstall_from_path(config_path, name),
+        Command::Cache { command } => cache::cache(command),
+        Command::Doctor => doctor::doctor(config_path, cli.proxy),
     }
 }
 
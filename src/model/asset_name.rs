@@ -1,4 +1,4 @@
-use crate::model::os::{OS, get_current_os};
+use crate::model::os::OS;
 
 /// Part of the name for each OS to identify proper asset
 #[derive(Debug, PartialEq, Eq)]
@@ -9,10 +9,12 @@ pub struct AssetName {
 }
 
 impl AssetName {
-    /// Get the current OS and extract the corresponding name
-    /// of the downloaded tool
-    pub fn get_name_by_os(&self) -> Option<&String> {
-        match get_current_os() {
+    /// Extract the name of the downloaded tool for the given OS
+    pub fn get_name_for_os(
+        &self,
+        os: OS,
+    ) -> Option<&String> {
+        match os {
             OS::Windows => self.windows.as_ref(),
             OS::MacOS => self.macos.as_ref(),
             OS::Linux => self.linux.as_ref(),
@@ -54,14 +56,17 @@ mod tests {
             windows: Some(String::from("powershell")),
         };
 
-        let name = asset_name.get_name_by_os();
-
-        if cfg!(target_os = "windows") {
-            assert_eq!(name, Some(&String::from("powershell")));
-        } else if cfg!(target_os = "macos") {
-            assert_eq!(name, Some(&String::from("fish")));
-        } else {
-            assert_eq!(name, Some(&String::from("oh-my-zsh")));
-        }
+        assert_eq!(
+            asset_name.get_name_for_os(OS::Windows),
+            Some(&String::from("powershell"))
+        );
+        assert_eq!(
+            asset_name.get_name_for_os(OS::MacOS),
+            Some(&String::from("fish"))
+        );
+        assert_eq!(
+            asset_name.get_name_for_os(OS::Linux),
+            Some(&String::from("oh-my-zsh"))
+        );
     }
 }
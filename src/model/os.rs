@@ -1,7 +1,8 @@
 use std::env;
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OS {
     Windows,
     #[allow(clippy::enum_variant_names)]
@@ -41,6 +42,22 @@ impl Display for OS {
     }
 }
 
+impl FromStr for OS {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "windows" => Ok(Self::Windows),
+            "macos" => Ok(Self::MacOS),
+            "linux" => Ok(Self::Linux),
+            _ => Err(format!(
+                "Unknown OS '{}', expected one of: linux, macos, windows",
+                s
+            )),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,4 +81,12 @@ mod tests {
         assert_eq!(OS::MacOS.to_string(), String::from("macos"));
         assert_eq!(OS::Linux.to_string(), String::from("linux"));
     }
+
+    #[test]
+    fn os_from_str() {
+        assert_eq!("windows".parse(), Ok(OS::Windows));
+        assert_eq!("macos".parse(), Ok(OS::MacOS));
+        assert_eq!("linux".parse(), Ok(OS::Linux));
+        assert!("freebsd".parse::<OS>().is_err());
+    }
 }
@@ -8,6 +8,20 @@ use crate::model::os::get_current_os;
 pub struct Release {
     pub tag_name: String,
     pub assets: Vec<Asset>,
+
+    /// Whether the release is marked as a prerelease on GitHub
+    #[serde(default)]
+    pub prerelease: bool,
+
+    /// Whether the release is an unpublished draft
+    #[serde(default)]
+    pub draft: bool,
+
+    /// Publication time (e.g. "2022-09-20T10:00:00Z"), missing for drafts
+    pub published_at: Option<String>,
+
+    /// Release notes
+    pub body: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Clone, Eq, PartialEq)]
@@ -15,6 +29,13 @@ pub struct Asset {
     pub id: u32,
     pub name: String,
     pub size: u64,
+
+    /// Last time the asset was uploaded. Moving tags (e.g. 'nightly') keep
+    /// the same tag name but get new assets, so this is used to detect changes.
+    pub updated_at: Option<String>,
+
+    /// Checksum of the asset (e.g. "sha256:<hex>") if GitHub reports it
+    pub digest: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -61,3 +82,46 @@ impl Display for AssetError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn release_from_json() {
+        let json = r#"{
+            "tag_name": "v0.10.0",
+            "prerelease": true,
+            "draft": false,
+            "published_at": "2022-09-20T10:00:00Z",
+            "body": "Release notes",
+            "assets": [
+                {
+                    "id": 42,
+                    "name": "tool-x86_64-unknown-linux-gnu.tar.gz",
+                    "size": 1024,
+                    "updated_at": "2022-09-20T09:00:00Z",
+                    "digest": "sha256:0123456789abcdef"
+                }
+            ]
+        }"#;
+
+        let release: Release = ureq::serde_json::from_str(json).unwrap();
+
+        assert_eq!(release.tag_name, "v0.10.0");
+        assert!(release.prerelease);
+        assert!(!release.draft);
+        assert_eq!(release.published_at, Some("2022-09-20T10:00:00Z".into()));
+        assert_eq!(release.body, Some("Release notes".into()));
+        assert_eq!(
+            release.assets,
+            vec![Asset {
+                id: 42,
+                name: "tool-x86_64-unknown-linux-gnu.tar.gz".into(),
+                size: 1024,
+                updated_at: Some("2022-09-20T09:00:00Z".into()),
+                digest: Some("sha256:0123456789abcdef".into()),
+            }]
+        );
+    }
+}
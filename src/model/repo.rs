@@ -18,7 +18,7 @@ impl Display for RepoError {
     ) -> std::fmt::Result {
         match self {
             RepoError::NotFound { owner, repo, tag } => match tag {
-                ToolInfoTag::Latest => {
+                ToolInfoTag::Latest | ToolInfoTag::Prerelease => {
                     write!(f, "The {owner}/{repo} doesn't exist or has no releases.")
                 }
                 _ => write!(
@@ -1,9 +1,13 @@
 use super::release::Asset;
 use crate::infra::client::Client;
 use crate::model::asset_name::AssetName;
+use crate::model::os::{OS, get_current_os};
 use crate::model::release::AssetError;
+use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
 
+// A few values are created per sync, so boxing 'ToolInfo' isn't worth it
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, PartialEq, Eq)]
 pub enum Tool {
     Known(ToolInfo),
@@ -39,8 +43,12 @@ impl Display for ToolError {
 pub enum ToolInfoTag {
     /// Download latest
     Latest,
+    /// Download the newest release, including prereleases
+    Prerelease,
     /// Download a specific version
     Specific(String),
+    /// Follow a moving tag (e.g. 'nightly') and redownload when its assets change
+    Channel(String),
 }
 
 const LATEST_VERSION: &str = "latest";
@@ -64,8 +72,82 @@ const COMPANION_EXTENSIONS: [&str; 14] = [
 impl ToolInfoTag {
     pub fn to_str_version(&self) -> String {
         match self {
-            Self::Latest => LATEST_VERSION.to_owned(),
-            Self::Specific(version) => format!("tags/{}", version),
+            Self::Latest | Self::Prerelease => LATEST_VERSION.to_owned(),
+            Self::Specific(version) | Self::Channel(version) => format!("tags/{}", version),
+        }
+    }
+}
+
+/// A single executable to install from the release asset
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Executable {
+    /// Executable name inside the .tar.gz or .zip archive
+    pub exe_name: String,
+
+    /// Name of the installed executable, the same as 'exe_name' unless it's
+    /// renamed
+    pub bin_name: String,
+}
+
+impl Executable {
+    pub fn new(exe_name: &str) -> Executable {
+        Executable {
+            exe_name: String::from(exe_name),
+            bin_name: String::from(exe_name),
+        }
+    }
+}
+
+/// Paths to shell completion scripts for each shell
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Completions {
+    pub bash: Option<String>,
+    pub zsh: Option<String>,
+    pub fish: Option<String>,
+}
+
+/// Files shipped in the asset besides executables. Paths are relative to the
+/// unpacked archive or to the directory inside it named after the asset.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Extras {
+    /// Man pages, e.g. "doc/rg.1"
+    #[serde(default)]
+    pub man_pages: Vec<String>,
+
+    /// Shell completion scripts, e.g. "complete/_rg" for zsh
+    #[serde(default)]
+    pub completions: Completions,
+}
+
+impl Extras {
+    /// Use paths from 'self' and fall back to 'other' for unspecified ones
+    pub fn or(
+        &self,
+        other: &Extras,
+    ) -> Extras {
+        Extras {
+            man_pages: if self.man_pages.is_empty() {
+                other.man_pages.clone()
+            } else {
+                self.man_pages.clone()
+            },
+            completions: Completions {
+                bash: self
+                    .completions
+                    .bash
+                    .clone()
+                    .or_else(|| other.completions.bash.clone()),
+                zsh: self
+                    .completions
+                    .zsh
+                    .clone()
+                    .or_else(|| other.completions.zsh.clone()),
+                fish: self
+                    .completions
+                    .fish
+                    .clone()
+                    .or_else(|| other.completions.fish.clone()),
+            },
         }
     }
 }
@@ -82,20 +164,57 @@ pub struct ToolInfo {
     /// Executable name inside the .tar.gz or .zip archive
     pub exe_name: String,
 
+    /// Name of the installed executable if it differs from 'exe_name'
+    pub bin_name: Option<String>,
+
+    /// All executables to install from the asset, only 'exe_name' if empty
+    pub exe_names: Vec<Executable>,
+
     /// Version tag
     pub tag: ToolInfoTag,
 
     /// Asset name depending on the OS
     pub asset_name: AssetName,
+
+    /// Path of the executable inside the archive as a glob with '{exe}' and
+    /// '{version}' placeholders, e.g. "*/bin/{exe}"
+    pub exe_path: Option<String>,
+
+    /// Man pages and completions inside the asset
+    pub extras: Extras,
 }
 
 impl ToolInfo {
+    /// Executables to install from the asset, the main one goes first
+    pub fn executables(&self) -> Vec<Executable> {
+        if self.exe_names.is_empty() {
+            vec![Executable {
+                exe_name: self.exe_name.clone(),
+                bin_name: self
+                    .bin_name
+                    .clone()
+                    .unwrap_or_else(|| self.exe_name.clone()),
+            }]
+        } else {
+            self.exe_names.clone()
+        }
+    }
+
     /// Select an Asset from all Assets based on which Operating System is used
     pub fn select_asset(
         &self,
         assets: &[Asset],
     ) -> Result<Asset, AssetError> {
-        match self.asset_name.get_name_by_os() {
+        self.select_asset_for_os(get_current_os(), assets)
+    }
+
+    /// Select an Asset from all Assets for the given Operating System
+    pub fn select_asset_for_os(
+        &self,
+        os: OS,
+        assets: &[Asset],
+    ) -> Result<Asset, AssetError> {
+        match self.asset_name.get_name_for_os(os) {
             None => Err(AssetError::OsSelectorUnknown),
             Some(asset_name) => {
                 let mut filtered_assets = assets
@@ -137,16 +256,36 @@ pub struct ToolAsset {
     /// **can't** be here.
     pub tag: String,
 
-    /// Executable name inside the .tar.gz or .zip archive
-    pub exe_name: String,
+    /// Executables to install from the asset, never empty
+    pub executables: Vec<Executable>,
+
+    /// Path of the executable inside the archive as a glob with '{exe}' and
+    /// '{version}' placeholders, e.g. "*/bin/{exe}"
+    pub exe_path: Option<String>,
+
+    /// Man pages and completions inside the asset
+    pub extras: Extras,
 
     /// The selected asset
     pub asset: Asset,
 
+    /// Whether the tag is a moving channel (e.g. "nightly"), so the asset is
+    /// downloaded only when it differs from the installed one
+    pub is_channel: bool,
+
     /// GitHub API client that produces the stream for downloading the asset
     pub client: Client,
 }
 
+impl ToolAsset {
+    /// Name of the main installed executable
+    pub fn exe_name(&self) -> &str {
+        self.executables
+            .first()
+            .map_or(self.tool_name.as_str(), |exe| exe.bin_name.as_str())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -159,7 +298,11 @@ mod tests {
             owner: "owner".to_string(),
             repo: "repo".to_string(),
             exe_name: "exe".to_string(),
+            bin_name: None,
+            exe_names: vec![],
             tag: ToolInfoTag::Latest,
+            exe_path: None,
+            extras: Extras::default(),
             asset_name: AssetName {
                 linux: Some(asset_name.to_string()),
                 macos: Some(asset_name.to_string()),
@@ -172,16 +315,22 @@ mod tests {
                 id: 1,
                 name: "1".to_string(),
                 size: 10,
+                updated_at: None,
+                digest: None,
             },
             Asset {
                 id: 2,
                 name: asset_name.to_string(),
                 size: 50,
+                updated_at: None,
+                digest: None,
             },
             Asset {
                 id: 3,
                 name: "3".to_string(),
                 size: 77,
+                updated_at: None,
+                digest: None,
             },
         ];
 
@@ -190,7 +339,9 @@ mod tests {
             Ok(Asset {
                 id: 2,
                 name: asset_name.to_string(),
-                size: 50
+                size: 50,
+                updated_at: None,
+                digest: None
             })
         );
     }
@@ -203,7 +354,11 @@ mod tests {
             owner: "owner".to_string(),
             repo: "repo".to_string(),
             exe_name: "exe".to_string(),
+            bin_name: None,
+            exe_names: vec![],
             tag: ToolInfoTag::Latest,
+            exe_path: None,
+            extras: Extras::default(),
             asset_name: AssetName {
                 linux: Some(asset_name.to_string()),
                 macos: Some(asset_name.to_string()),
@@ -216,26 +371,36 @@ mod tests {
                 id: 1,
                 name: "asset_1".to_string(),
                 size: 10,
+                updated_at: None,
+                digest: None,
             },
             Asset {
                 id: 2,
                 name: "asset_2".to_string(),
                 size: 50,
+                updated_at: None,
+                digest: None,
             },
             Asset {
                 id: 3,
                 name: "asset_3".to_string(),
                 size: 77,
+                updated_at: None,
+                digest: None,
             },
             Asset {
                 id: 3,
                 name: "asset_3.asc".to_string(),
                 size: 12,
+                updated_at: None,
+                digest: None,
             },
             Asset {
                 id: 3,
                 name: "not a match".to_string(),
                 size: 77,
+                updated_at: None,
+                digest: None,
             },
         ];
 
@@ -257,7 +422,11 @@ mod tests {
             owner: "owner".to_string(),
             repo: "repo".to_string(),
             exe_name: "exe".to_string(),
+            bin_name: None,
+            exe_names: vec![],
             tag: ToolInfoTag::Latest,
+            exe_path: None,
+            extras: Extras::default(),
             asset_name: AssetName {
                 linux: Some(asset_name.to_string()),
                 macos: Some(asset_name.to_string()),
@@ -270,16 +439,22 @@ mod tests {
                 id: 1,
                 name: "1".to_string(),
                 size: 10,
+                updated_at: None,
+                digest: None,
             },
             Asset {
                 id: 2,
                 name: "2".to_string(),
                 size: 50,
+                updated_at: None,
+                digest: None,
             },
             Asset {
                 id: 3,
                 name: "3".to_string(),
                 size: 77,
+                updated_at: None,
+                digest: None,
             },
         ];
 
@@ -295,7 +470,11 @@ mod tests {
             owner: "owner".to_string(),
             repo: "repo".to_string(),
             exe_name: "exe".to_string(),
+            bin_name: None,
+            exe_names: vec![],
             tag: ToolInfoTag::Latest,
+            exe_path: None,
+            extras: Extras::default(),
             asset_name: AssetName {
                 linux: None,
                 macos: None,
@@ -308,4 +487,44 @@ mod tests {
             Err(AssetError::OsSelectorUnknown)
         );
     }
+
+    #[test]
+    fn executables_default_to_exe_name() {
+        let mut tool_info = ToolInfo {
+            owner: "FiloSottile".to_string(),
+            repo: "age".to_string(),
+            exe_name: "age".to_string(),
+            bin_name: None,
+            exe_names: vec![],
+            tag: ToolInfoTag::Latest,
+            exe_path: None,
+            extras: Extras::default(),
+            asset_name: AssetName {
+                linux: None,
+                macos: None,
+                windows: None,
+            },
+        };
+
+        assert_eq!(tool_info.executables(), vec![Executable::new("age")]);
+
+        tool_info.bin_name = Some("rage".to_string());
+        assert_eq!(
+            tool_info.executables(),
+            vec![Executable {
+                exe_name: "age".to_string(),
+                bin_name: "rage".to_string(),
+            }]
+        );
+
+        tool_info.exe_names = vec![
+            Executable::new("age"),
+            Executable {
+                exe_name: "age-keygen".to_string(),
+                bin_name: "keygen".to_string(),
+            },
+        ];
+
+        assert_eq!(tool_info.executables(), tool_info.exe_names);
+    }
 }
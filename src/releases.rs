@@ -0,0 +1,217 @@
+use console::{Emoji, style};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::config::schema::Config;
+use crate::config::toml;
+use crate::infra::client::Client;
+use crate::infra::err;
+use crate::infra::rate_limit::RateLimit;
+use crate::infra::retry::RetryPolicy;
+use crate::model::release::Release;
+use crate::model::tool::{Tool, ToolInfo};
+use crate::sync::configure::configure_tool;
+use crate::sync::db::{fmt_tool_names_info, lookup_tool};
+
+const PACKAGE: Emoji<'_, '_> = Emoji("📦 ", "# ");
+const FOUND: Emoji<'_, '_> = Emoji("✅ ", "OK ");
+const NOT_FOUND: Emoji<'_, '_> = Emoji("❌ ", "NO ");
+
+/// GitHub returns at most 100 releases per page
+const RELEASES_PER_PAGE: usize = 100;
+
+/// Show recent releases of a single tool
+pub fn releases(
+    config_path: PathBuf,
+    name: String,
+    limit: usize,
+    notes: bool,
+    proxy: Option<String>,
+    wait_on_rate_limit: bool,
+) {
+    toml::with_parsed_file(config_path, proxy, wait_on_rate_limit, false, |config| {
+        show_releases(config, name, limit, notes)
+    })
+}
+
+fn show_releases(
+    config: Config,
+    name: String,
+    limit: usize,
+    notes: bool,
+) {
+    let tool_info = resolve_tool(&config, &name);
+
+    let proxy = config.proxy.as_ref().map(|p| {
+        ureq::Proxy::new(p).unwrap_or_else(|e| {
+            err::abort_with(format!("Could not parse proxy address '{}': {}", p, e))
+        })
+    });
+
+    let client = Client {
+        owner: tool_info.owner.clone(),
+        repo: tool_info.repo.clone(),
+        version: tool_info.tag.to_str_version(),
+        proxy,
+        retry: RetryPolicy::with_retries(config.retries),
+        rate_limit: Rc::new(RateLimit::new(config.wait_on_rate_limit)),
+    };
+
+    let max_pages = limit.div_ceil(RELEASES_PER_PAGE);
+    let releases = match client.list_releases(max_pages) {
+        Ok(releases) => releases,
+        Err(e) => err::abort_with(format!(
+            "Unable to fetch releases of {}/{}: {}",
+            tool_info.owner, tool_info.repo, e
+        )),
+    };
+
+    println!(
+        "{}{}",
+        PACKAGE,
+        style(format!("{}/{}", tool_info.owner, tool_info.repo)).bold()
+    );
+
+    if releases.is_empty() {
+        println!("No releases found");
+        return;
+    }
+
+    let releases: Vec<&Release> = releases.iter().take(limit).collect();
+    let tag_width = releases
+        .iter()
+        .map(|release| release.tag_name.len())
+        .max()
+        .unwrap_or_default();
+
+    for release in releases {
+        let asset = match tool_info.select_asset(&release.assets) {
+            Ok(asset) => format!("{}{}", FOUND, asset.name),
+            Err(e) => format!("{}{}", NOT_FOUND, style(e).dim()),
+        };
+
+        println!(
+            "  {tag:tag_width$}  {date:10}  {marker:10}  {asset}",
+            tag = style(&release.tag_name).cyan().bold(),
+            date = published_date(release),
+            marker = release_marker(release),
+            asset = asset,
+            tag_width = tag_width,
+        );
+
+        if notes && let Some(summary) = notes_summary(release) {
+            println!(
+                "  {:tag_width$}  {}",
+                "",
+                style(summary).dim(),
+                tag_width = tag_width
+            );
+        }
+    }
+}
+
+/// Resolve the tool from the config first and from the hardcoded database
+/// if it's not configured
+fn resolve_tool(
+    config: &Config,
+    name: &str,
+) -> ToolInfo {
+    match config.tools.get(name) {
+        Some(config_asset) => match configure_tool(name, config_asset) {
+            Tool::Known(tool_info) => tool_info,
+            Tool::Error(e) => err::abort_with(format!("{} {}", name, e)),
+        },
+        None => match lookup_tool(name) {
+            Some(tool_info) => tool_info,
+            None => {
+                let tools = fmt_tool_names_info(|name| format!("    * {name}"));
+
+                err::abort(format!(
+                    r#"The '{name}' tool is neither configured nor supported natively.
+Supported tools:
+{tools}"#
+                ))
+            }
+        },
+    }
+}
+
+/// Date part of the publication time, e.g. "2022-09-20"
+fn published_date(release: &Release) -> &str {
+    match &release.published_at {
+        Some(published_at) => published_at.get(..10).unwrap_or(published_at),
+        None => "",
+    }
+}
+
+/// First non-empty line of the release notes
+fn notes_summary(release: &Release) -> Option<&str> {
+    release
+        .body
+        .as_deref()?
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+}
+
+fn release_marker(release: &Release) -> &'static str {
+    if release.draft {
+        "draft"
+    } else if release.prerelease {
+        "prerelease"
+    } else {
+        ""
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mk_release(
+        published_at: Option<&str>,
+        prerelease: bool,
+        draft: bool,
+    ) -> Release {
+        Release {
+            tag_name: String::from("v1.0.0"),
+            assets: vec![],
+            prerelease,
+            draft,
+            published_at: published_at.map(String::from),
+            body: None,
+        }
+    }
+
+    #[test]
+    fn release_date_and_marker() {
+        let release = mk_release(Some("2022-09-20T10:00:00Z"), true, false);
+
+        assert_eq!(published_date(&release), "2022-09-20");
+        assert_eq!(release_marker(&release), "prerelease");
+    }
+
+    #[test]
+    fn draft_release_date_and_marker() {
+        let release = mk_release(None, true, true);
+
+        assert_eq!(published_date(&release), "");
+        assert_eq!(release_marker(&release), "draft");
+    }
+
+    #[test]
+    fn stable_release_marker() {
+        let release = mk_release(Some("2022-09-20T10:00:00Z"), false, false);
+
+        assert_eq!(release_marker(&release), "");
+    }
+
+    #[test]
+    fn first_line_of_notes() {
+        let mut release = mk_release(None, false, false);
+        assert_eq!(notes_summary(&release), None);
+
+        release.body = Some(String::from("\r\n## Fixes\r\n* Crash on start\r\n"));
+        assert_eq!(notes_summary(&release), Some("## Fixes"));
+    }
+}
@@ -1,15 +1,17 @@
 use deko::AnyDecoder;
 use std::fmt::{Display, Formatter};
 use std::fs::File;
-use std::io::BufReader;
-use std::path::{Path, PathBuf};
+use std::io::{BufReader, Read};
+use std::path::{Component, Path, PathBuf};
 
 use crate::model::asset_name::mk_exe_name;
+use crate::model::tool::Executable;
+
+use super::package::{unpack_deb, unpack_rpm};
 
 pub struct Archive<'a> {
     archive_path: &'a PathBuf,
     tmp_dir: &'a Path,
-    exe_name: &'a str,
     tag: &'a str,
     archive_type: ArchiveType<'a>,
 }
@@ -20,12 +22,43 @@ enum ArchiveType<'a> {
     Exe(&'a str),
     Zip(&'a str),
     TarBall(&'a str),
+    /// Single compressed executable, the name after decompression
+    Compressed(&'a str),
+    Deb(&'a str),
+    Rpm(&'a str),
+}
+
+/// Extensions of tarballs, used when the format isn't detected from content
+const TARBALL_EXTENSIONS: [&str; 10] = [
+    "tar", "gz", "tgz", "xz", "txz", "bz2", "tbz", "tbz2", "zst", "tzst",
+];
+
+/// Archive format detected from the magic bytes at the start of the file
+#[derive(Debug, PartialEq, Eq)]
+enum ArchiveFormat {
+    Zip,
+    /// Compressed with gzip, xz, bzip2 or zstd, or uncompressed tarball
+    TarBall,
+    /// Compressed stream that isn't a tarball
+    Compressed,
+    Deb,
+    Rpm,
 }
 
 pub enum UnpackError {
     IOError(std::io::Error),
     ZipError(zip::result::ZipError),
-    ExeNotFound(String),
+    ExeNotFound {
+        exe_name: String,
+        archive: String,
+        /// Executable files found in the archive to help fixing the config
+        executables: Vec<String>,
+    },
+    SeveralExesFound {
+        exe_name: String,
+        archive: String,
+        candidates: Vec<String>,
+    },
 }
 
 impl Display for UnpackError {
@@ -36,8 +69,39 @@ impl Display for UnpackError {
         match self {
             UnpackError::IOError(e) => write!(f, "{}", e),
             UnpackError::ZipError(e) => write!(f, "{}", e),
-            UnpackError::ExeNotFound(archive_name) => {
-                write!(f, "Can't find executable in archive: {}", archive_name)
+            UnpackError::ExeNotFound {
+                exe_name,
+                archive,
+                executables,
+            } => {
+                write!(
+                    f,
+                    "Can't find executable '{}' in archive: {}",
+                    exe_name, archive
+                )?;
+
+                if executables.is_empty() {
+                    write!(f, " (the archive has no executable files)")
+                } else {
+                    write!(
+                        f,
+                        " (executable files in the archive: {}). Use 'exe_path' to specify the path",
+                        executables.join(", ")
+                    )
+                }
+            }
+            UnpackError::SeveralExesFound {
+                exe_name,
+                archive,
+                candidates,
+            } => {
+                write!(
+                    f,
+                    "Found several executables '{}' in archive {}: {}. Use 'exe_path' to pick one",
+                    exe_name,
+                    archive,
+                    candidates.join(", ")
+                )
             }
         }
     }
@@ -47,17 +111,15 @@ impl<'a> Archive<'a> {
     pub fn from(
         archive_path: &'a PathBuf,
         tmp_dir: &'a Path,
-        exe_name: &'a str,
         asset_name: &'a str,
         tag: &'a str,
-    ) -> Option<Archive<'a>> {
-        match asset_name.rsplit_once('.') {
+    ) -> std::io::Result<Option<Archive<'a>>> {
+        let archive = match asset_name.rsplit_once('.') {
             None | Some((_, "exe")) => {
                 // un-compressed binary
                 Archive {
                     archive_path,
                     tmp_dir,
-                    exe_name,
                     tag,
                     archive_type: ArchiveType::Exe(asset_name),
                 }
@@ -68,7 +130,6 @@ impl<'a> Archive<'a> {
                 Archive {
                     archive_path,
                     tmp_dir,
-                    exe_name,
                     tag,
                     archive_type: ArchiveType::Exe(asset_name),
                 }
@@ -77,118 +138,510 @@ impl<'a> Archive<'a> {
             Some((_, "AppImage")) => Archive {
                 archive_path,
                 tmp_dir,
-                exe_name,
                 tag,
                 archive_type: ArchiveType::AppImage(asset_name),
             }
             .into(),
-            Some((prefix, "zip")) => Archive {
-                archive_path,
-                tmp_dir,
-                exe_name,
-                tag,
-                archive_type: ArchiveType::Zip(prefix),
-            }
-            .into(),
-            Some((prefix, "xz" | "gz" | "tgz")) => Archive {
-                archive_path,
-                tmp_dir,
-                exe_name,
-                tag,
-                archive_type: ArchiveType::TarBall(prefix.trim_end_matches(".tar")),
-            }
-            .into(),
-            _ => {
-                dbg!("unsupported asset format {}", &asset_name);
-                None
+            Some((prefix, ext)) => {
+                // the content is more reliable than the extension
+                let detected_format = detect_format(archive_path, is_tarball_name(prefix, ext))?;
+                let archive_type = match (detected_format, ext) {
+                    (Some(ArchiveFormat::Zip), _) | (None, "zip") => ArchiveType::Zip(prefix),
+                    (Some(ArchiveFormat::TarBall), _) => {
+                        ArchiveType::TarBall(prefix.trim_end_matches(".tar"))
+                    }
+                    (Some(ArchiveFormat::Compressed), _) => ArchiveType::Compressed(prefix),
+                    (Some(ArchiveFormat::Deb), _) | (None, "deb") => ArchiveType::Deb(prefix),
+                    (Some(ArchiveFormat::Rpm), _) | (None, "rpm") => ArchiveType::Rpm(prefix),
+                    (None, ext) if TARBALL_EXTENSIONS.contains(&ext) => {
+                        ArchiveType::TarBall(prefix.trim_end_matches(".tar"))
+                    }
+                    (None, _) => {
+                        dbg!("unsupported asset format {}", &asset_name);
+                        return Ok(None);
+                    }
+                };
+
+                Archive {
+                    archive_path,
+                    tmp_dir,
+                    tag,
+                    archive_type,
+                }
+                .into()
             }
-        }
+        };
+
+        Ok(archive)
     }
 
-    /// Unpack archive and return path to the executable tool
-    pub fn unpack(&self) -> Result<PathBuf, UnpackError> {
+    /// Unpack archive and return paths to the executables in the same order.
+    /// The 'exe_path' glob replaces guessing the path inside the archive.
+    pub fn unpack(
+        &self,
+        executables: &[Executable],
+        exe_path: Option<&str>,
+    ) -> Result<Vec<PathBuf>, UnpackError> {
         match self.archive_type {
             // already .AppImage file: no need to unpack
-            ArchiveType::AppImage(app_image) => Ok(self.tmp_dir.join(app_image)),
+            ArchiveType::AppImage(app_image) => Ok(self.same_file(app_image, executables)),
 
             // already .exe file without archive (on Windows): no need to unpack
-            ArchiveType::Exe(exe_file) => Ok(self.tmp_dir.join(exe_file)),
+            ArchiveType::Exe(exe_file) => Ok(self.same_file(exe_file, executables)),
+
+            // decompress the executable
+            ArchiveType::Compressed(exe_file) => {
+                decompress_file(self.archive_path, &self.tmp_dir.join(exe_file))
+                    .map_err(UnpackError::IOError)?;
+                Ok(self.same_file(exe_file, executables))
+            }
 
             // unpack .tar ball
             ArchiveType::TarBall(asset_name) => {
-                unpack_tar(self.archive_path, self.tmp_dir).map_err(UnpackError::IOError)?;
-                find_path_to_exe(
-                    self.archive_path,
-                    self.tmp_dir,
-                    self.exe_name,
-                    asset_name,
-                    self.tag,
-                )
+                let files =
+                    unpack_tar(self.archive_path, self.tmp_dir).map_err(UnpackError::IOError)?;
+                self.find_executables(asset_name, &files, executables, exe_path)
             }
 
             // unpack .zip archive
             ArchiveType::Zip(asset_name) => {
-                unpack_zip(self.archive_path, self.tmp_dir)?;
-                find_path_to_exe(
-                    self.archive_path,
-                    self.tmp_dir,
-                    self.exe_name,
-                    asset_name,
-                    self.tag,
-                )
+                let files = unpack_zip(self.archive_path, self.tmp_dir)?;
+                self.find_executables(asset_name, &files, executables, exe_path)
+            }
+
+            // extract files of .deb package
+            ArchiveType::Deb(asset_name) => {
+                let files =
+                    unpack_deb(self.archive_path, self.tmp_dir).map_err(UnpackError::IOError)?;
+                self.find_executables(asset_name, &files, executables, exe_path)
+            }
+
+            // extract files of .rpm package
+            ArchiveType::Rpm(asset_name) => {
+                let files =
+                    unpack_rpm(self.archive_path, self.tmp_dir).map_err(UnpackError::IOError)?;
+                self.find_executables(asset_name, &files, executables, exe_path)
+            }
+        }
+    }
+
+    /// Path to a file besides executables in the unpacked archive, relative
+    /// to the archive root or to the directory named after the asset
+    pub fn find_extra(
+        &self,
+        path: &str,
+    ) -> Option<PathBuf> {
+        match self.archive_type {
+            ArchiveType::AppImage(_) | ArchiveType::Exe(_) | ArchiveType::Compressed(_) => None,
+            ArchiveType::TarBall(asset_name)
+            | ArchiveType::Zip(asset_name)
+            | ArchiveType::Deb(asset_name)
+            | ArchiveType::Rpm(asset_name) => [
+                self.tmp_dir.join(path),
+                self.tmp_dir.join(asset_name).join(path),
+            ]
+            .into_iter()
+            .find(|extra_path| extra_path.is_file()),
+        }
+    }
+
+    /// The asset itself is the executable
+    fn same_file(
+        &self,
+        file_name: &str,
+        executables: &[Executable],
+    ) -> Vec<PathBuf> {
+        executables
+            .iter()
+            .map(|_| self.tmp_dir.join(file_name))
+            .collect()
+    }
+
+    /// Find executables among 'files' unpacked from the archive
+    fn find_executables(
+        &self,
+        asset_name: &str,
+        files: &[String],
+        executables: &[Executable],
+        exe_path: Option<&str>,
+    ) -> Result<Vec<PathBuf>, UnpackError> {
+        executables
+            .iter()
+            .map(|exe| {
+                let found = match exe_path {
+                    Some(exe_path) => {
+                        let pattern = expand_exe_path(exe_path, &exe.exe_name, self.tag);
+                        find_matching_path(self.tmp_dir, files, &pattern)
+                    }
+                    None => {
+                        match find_path_to_exe(self.tmp_dir, &exe.exe_name, asset_name, self.tag) {
+                            Some(path) => Some(path),
+                            None => self.search_exe(files, &exe.exe_name)?,
+                        }
+                    }
+                };
+
+                found.ok_or_else(|| UnpackError::ExeNotFound {
+                    exe_name: exe.exe_name.clone(),
+                    archive: format!("{}", self.archive_path.display()),
+                    executables: executable_files(self.tmp_dir, files),
+                })
+            })
+            .collect()
+    }
+
+    /// Look for the executable by name among all unpacked files when it isn't
+    /// at any of the usual paths. Files that can't be executed are ignored if
+    /// there are several candidates.
+    fn search_exe(
+        &self,
+        files: &[String],
+        exe_name: &str,
+    ) -> Result<Option<PathBuf>, UnpackError> {
+        let exe_file_name = mk_exe_name(exe_name);
+
+        let mut candidates: Vec<&String> = files
+            .iter()
+            .filter(|file| file.rsplit('/').next() == Some(exe_file_name.as_str()))
+            .filter(|file| self.tmp_dir.join(file).is_file())
+            .collect();
+        candidates.sort();
+
+        if candidates.len() > 1 {
+            let executables: Vec<&String> = candidates
+                .iter()
+                .copied()
+                .filter(|file| is_executable(&self.tmp_dir.join(file)))
+                .collect();
+
+            if !executables.is_empty() {
+                candidates = executables;
             }
         }
+
+        match candidates.as_slice() {
+            [] => Ok(None),
+            [file] => Ok(Some(self.tmp_dir.join(file))),
+            _ => Err(UnpackError::SeveralExesFound {
+                exe_name: String::from(exe_name),
+                archive: format!("{}", self.archive_path.display()),
+                candidates: candidates.into_iter().cloned().collect(),
+            }),
+        }
     }
 }
 
+/// Whether the asset name says it's a tarball, e.g. "tool.tar.gz" or "tool.tgz"
+fn is_tarball_name(
+    prefix: &str,
+    ext: &str,
+) -> bool {
+    prefix.ends_with(".tar") || matches!(ext, "tar" | "tgz" | "txz" | "tbz" | "tbz2" | "tzst")
+}
+
+/// Detect the archive format from its first bytes, `None` if it's unknown. A
+/// compressed stream is a tarball if it has the tar magic (missing in old tar
+/// formats) or 'is_tarball_name' is set.
+fn detect_format(
+    archive_path: &Path,
+    is_tarball_name: bool,
+) -> std::io::Result<Option<ArchiveFormat>> {
+    let header = File::open(archive_path).and_then(read_header)?;
+
+    let is_zip = header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06");
+    let is_compressed = COMPRESSION_MAGIC
+        .iter()
+        .any(|magic| header.starts_with(magic));
+
+    let format = if is_zip {
+        Some(ArchiveFormat::Zip)
+    } else if header.starts_with(b"!<arch>\n") {
+        Some(ArchiveFormat::Deb)
+    } else if header.starts_with(&[0xed, 0xab, 0xee, 0xdb]) {
+        Some(ArchiveFormat::Rpm)
+    } else if is_compressed {
+        // look inside the stream to tell tarballs from compressed executables
+        let decompressed_header = File::open(archive_path)
+            .and_then(|file| read_header(AnyDecoder::new(BufReader::new(file))))?;
+
+        if is_tarball_name || is_tar_header(&decompressed_header) {
+            Some(ArchiveFormat::TarBall)
+        } else {
+            Some(ArchiveFormat::Compressed)
+        }
+    } else if is_tar_header(&header) {
+        Some(ArchiveFormat::TarBall)
+    } else {
+        None
+    };
+
+    Ok(format)
+}
+
+/// Read enough bytes from the start of the file to detect its format
+fn read_header(reader: impl Read) -> std::io::Result<Vec<u8>> {
+    let mut header = Vec::with_capacity(TAR_MAGIC_OFFSET + TAR_MAGIC.len());
+    reader
+        .take((TAR_MAGIC_OFFSET + TAR_MAGIC.len()) as u64)
+        .read_to_end(&mut header)?;
+    Ok(header)
+}
+
+fn is_tar_header(header: &[u8]) -> bool {
+    header.get(TAR_MAGIC_OFFSET..) == Some(TAR_MAGIC)
+}
+
+/// Magic bytes of gzip (with deflate, the only method), xz, bzip2 and zstd
+/// streams
+const COMPRESSION_MAGIC: [&[u8]; 4] = [
+    &[0x1f, 0x8b, 0x08],
+    &[0xfd, b'7', b'z', b'X', b'Z', 0x00],
+    b"BZh",
+    &[0x28, 0xb5, 0x2f, 0xfd],
+];
+
+/// Tarballs have "ustar" in the header of the first entry
+const TAR_MAGIC_OFFSET: usize = 257;
+const TAR_MAGIC: &[u8] = b"ustar";
+
+/// Decompress a single gzip, xz, bzip2 or zstd stream into 'file_path'
+fn decompress_file(
+    compressed_path: &Path,
+    file_path: &Path,
+) -> Result<(), std::io::Error> {
+    let compressed_file = File::open(compressed_path)?;
+    let mut decoder = AnyDecoder::new(BufReader::new(compressed_file));
+    let mut file = File::create(file_path)?;
+    std::io::copy(&mut decoder, &mut file)?;
+    Ok(())
+}
+
+/// Unpack the tarball and return paths of unpacked files relative to
+/// 'tmp_dir'
 fn unpack_tar(
     tar_path: &PathBuf,
     tmp_dir: &Path,
-) -> Result<(), std::io::Error> {
+) -> Result<Vec<String>, std::io::Error> {
     // unpack tar_path to tmp_dir
     let tar_file = File::open(tar_path)?;
-    let tar_decoder = AnyDecoder::new(BufReader::new(tar_file));
-    let mut archive = tar::Archive::new(tar_decoder);
-    archive.unpack(tmp_dir)
+    unpack_tar_stream(AnyDecoder::new(BufReader::new(tar_file)), tmp_dir)
+}
+
+/// Unpack the decompressed tarball and return paths of unpacked files
+/// relative to 'tmp_dir'
+pub fn unpack_tar_stream(
+    tar_stream: impl Read,
+    tmp_dir: &Path,
+) -> Result<Vec<String>, std::io::Error> {
+    let mut archive = tar::Archive::new(tar_stream);
+
+    let mut files = Vec::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let is_dir = entry.header().entry_type().is_dir();
+        let path = normalize_entry_path(&entry.path()?);
+
+        // entries outside of 'tmp_dir' are skipped by 'unpack_in'
+        if entry.unpack_in(tmp_dir)? && !is_dir {
+            files.push(path);
+        }
+    }
+
+    Ok(files)
 }
 
+/// Unpack the zip archive and return paths of unpacked files relative to
+/// 'tmp_dir'
 fn unpack_zip(
     zip_path: &PathBuf,
     tmp_dir: &Path,
-) -> Result<(), UnpackError> {
+) -> Result<Vec<String>, UnpackError> {
     let zip_archive_file = File::open(zip_path).map_err(UnpackError::IOError)?;
 
     let mut archive = zip::ZipArchive::new(zip_archive_file).map_err(UnpackError::ZipError)?;
 
-    archive.extract(tmp_dir).map_err(UnpackError::ZipError)
+    let files = archive
+        .file_names()
+        .filter(|file_name| !file_name.ends_with('/'))
+        .map(|file_name| normalize_entry_path(Path::new(file_name)))
+        .collect();
+
+    archive.extract(tmp_dir).map_err(UnpackError::ZipError)?;
+    Ok(files)
+}
+
+/// Path of an archive entry with '/' separators and without leading "./"
+pub fn normalize_entry_path(path: &Path) -> String {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
 }
 
 fn find_path_to_exe(
-    archive_path: &Path,
     tmp_dir: &Path,
     exe_name: &str,
     asset_name: &str,
     tag: &str,
-) -> Result<PathBuf, UnpackError> {
-    let path_candidates = exe_paths(exe_name, asset_name, tag);
+) -> Option<PathBuf> {
+    exe_paths(exe_name, asset_name, tag)
+        .into_iter()
+        .map(|path| tmp_dir.join(path))
+        .find(|tool_path| tool_path.is_file())
+}
 
-    // find a path
-    for path in path_candidates {
-        // create path to the final executable
-        let mut tool_path = PathBuf::new();
-        tool_path.push(tmp_dir);
-        tool_path.push(path);
+/// Replace '{exe}' and '{version}' placeholders in the 'exe_path' glob
+fn expand_exe_path(
+    exe_path: &str,
+    exe_name: &str,
+    tag: &str,
+) -> String {
+    exe_path
+        .replace("{exe}", &mk_exe_name(exe_name))
+        .replace("{version}", tag.trim_start_matches('v'))
+}
 
-        // check if this path actually exists
-        if tool_path.is_file() {
-            return Ok(tool_path);
+/// First unpacked file matching the glob 'pattern' in alphabetical order
+fn find_matching_path(
+    tmp_dir: &Path,
+    files: &[String],
+    pattern: &str,
+) -> Option<PathBuf> {
+    let mut matching: Vec<&String> = files
+        .iter()
+        .filter(|file| glob_match(pattern, file))
+        .collect();
+    matching.sort();
+
+    matching
+        .into_iter()
+        .map(|file| tmp_dir.join(file))
+        .find(|path| path.is_file())
+}
+
+/// Match a path against a glob where '*' and '?' match inside a single path
+/// component and '**' matches any number of components
+fn glob_match(
+    pattern: &str,
+    path: &str,
+) -> bool {
+    let pattern: Vec<&str> = pattern
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect();
+    let path: Vec<&str> = path.split('/').collect();
+
+    match_components(&pattern, &path)
+}
+
+fn match_components(
+    pattern: &[&str],
+    path: &[&str],
+) -> bool {
+    wildcard_match(
+        pattern,
+        path,
+        |part| *part == "**",
+        |part, component| match_component(part.as_bytes(), component.as_bytes()),
+    )
+}
+
+fn match_component(
+    pattern: &[u8],
+    name: &[u8],
+) -> bool {
+    wildcard_match(
+        pattern,
+        name,
+        |c| *c == b'*',
+        |c, name_c| *c == b'?' || c == name_c,
+    )
+}
+
+/// Match 'items' against 'pattern' where star elements match any number of
+/// items and other elements match a single item. Only the last star is
+/// backtracked to: a later star can match anything an earlier one would, so
+/// this runs in O(pattern * items) steps instead of exponential time.
+fn wildcard_match<P, T>(
+    pattern: &[P],
+    items: &[T],
+    is_star: impl Fn(&P) -> bool,
+    matches: impl Fn(&P, &T) -> bool,
+) -> bool {
+    let (mut p, mut i) = (0, 0);
+    // pattern position after the last star and the item position it's matched up to
+    let mut last_star: Option<(usize, usize)> = None;
+
+    while i < items.len() {
+        if p < pattern.len() && is_star(&pattern[p]) {
+            p += 1;
+            last_star = Some((p, i));
+        } else if p < pattern.len() && matches(&pattern[p], &items[i]) {
+            p += 1;
+            i += 1;
+        } else if let Some((star_p, star_i)) = last_star {
+            // let the last star match one more item
+            p = star_p;
+            i = star_i + 1;
+            last_star = Some((star_p, i));
+        } else {
+            return false;
         }
     }
 
-    Err(UnpackError::ExeNotFound(format!(
-        "{}",
-        archive_path.display()
-    )))
+    pattern[p..].iter().all(is_star)
+}
+
+/// Unpacked files that can be executed, sorted by path
+fn executable_files(
+    tmp_dir: &Path,
+    files: &[String],
+) -> Vec<String> {
+    let mut executables: Vec<String> = files
+        .iter()
+        .filter(|file| is_executable(&tmp_dir.join(file)))
+        .cloned()
+        .collect();
+    executables.sort();
+    executables
+}
+
+/// Whether the file is marked as executable or is a native binary. Archives
+/// created on Windows don't keep Unix permissions.
+fn is_executable(path: &Path) -> bool {
+    path.is_file() && (has_exec_permissions(path) || has_binary_magic(path))
+}
+
+#[cfg(target_family = "unix")]
+fn has_exec_permissions(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .is_ok_and(|metadata| metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(target_family = "windows")]
+fn has_exec_permissions(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "exe")
+}
+
+/// Check for ELF, Mach-O (including universal binaries) and PE headers
+fn has_binary_magic(path: &Path) -> bool {
+    let mut magic = [0u8; 4];
+    let is_read = File::open(path).and_then(|mut file| file.read_exact(&mut magic));
+
+    is_read.is_ok()
+        && (magic == *b"\x7fELF"
+            || matches!(
+                magic,
+                [0xfe, 0xed, 0xfa, 0xce | 0xcf]
+                    | [0xce | 0xcf, 0xfa, 0xed, 0xfe]
+                    | [0xca, 0xfe, 0xba, 0xbe]
+            )
+            || magic.starts_with(b"MZ"))
 }
 
 // List of potential paths where an executable can be inside the archive
@@ -211,6 +664,7 @@ fn exe_paths(
         ["tmp", asset_name, &exe_name].iter().collect(),
         [&exe_name, &exe_name].iter().collect(),
         ["bin", &exe_name].iter().collect(),
+        ["usr", "bin", &exe_name].iter().collect(),
         [asset_name, "bin", &exe_name].iter().collect(),
         [&format!("{exe_name}-{tag}"), &exe_name].iter().collect(),
         [&format!("{exe_name}-{tag}-x86_64"), "usr/bin", &exe_name]
@@ -228,3 +682,397 @@ fn exe_paths(
             .collect(),
     ]
 }
+
+/// Compress the content with gzip, shared by tests of all archive formats
+#[cfg(test)]
+pub fn gzip(content: &[u8]) -> Vec<u8> {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(content).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    #[test]
+    fn several_executables_are_found() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let source_dir = tmp_dir.path().join("age");
+        fs::create_dir(&source_dir).unwrap();
+        fs::write(source_dir.join(mk_exe_name("age")), "age").unwrap();
+        fs::write(source_dir.join(mk_exe_name("age-keygen")), "keygen").unwrap();
+
+        let archive_path = tmp_dir.path().join("age-v1.1.1-linux-amd64.tar");
+        let mut builder = tar::Builder::new(fs::File::create(&archive_path).unwrap());
+        builder
+            .append_dir_all("age-v1.1.1-linux-amd64", &source_dir)
+            .unwrap();
+        builder.finish().unwrap();
+        drop(builder);
+
+        let unpack_dir = tmp_dir.path().join("unpacked");
+        fs::create_dir(&unpack_dir).unwrap();
+
+        let archive = Archive {
+            archive_path: &archive_path,
+            tmp_dir: &unpack_dir,
+            tag: "v1.1.1",
+            archive_type: ArchiveType::TarBall("age-v1.1.1-linux-amd64"),
+        };
+
+        let executables = vec![
+            Executable::new("age"),
+            Executable {
+                exe_name: String::from("age-keygen"),
+                bin_name: String::from("keygen"),
+            },
+        ];
+        let paths = archive.unpack(&executables, None).ok().unwrap();
+
+        assert_eq!(
+            paths,
+            vec![
+                unpack_dir
+                    .join("age-v1.1.1-linux-amd64")
+                    .join(mk_exe_name("age")),
+                unpack_dir
+                    .join("age-v1.1.1-linux-amd64")
+                    .join(mk_exe_name("age-keygen"))
+            ]
+        );
+
+        let missing = archive.unpack(&[Executable::new("age-plugin")], None);
+        assert!(matches!(
+            missing,
+            Err(UnpackError::ExeNotFound { exe_name, .. }) if exe_name == "age-plugin"
+        ));
+    }
+
+    #[test]
+    fn exe_path_glob() {
+        assert!(glob_match("*/bin/rg", "ripgrep-13.0.0/bin/rg"));
+        assert!(glob_match("./ripgrep-*/rg", "ripgrep-13.0.0/rg"));
+        assert!(glob_match("**/rg", "rg"));
+        assert!(glob_match("**/rg", "a/b/c/rg"));
+        assert!(glob_match("rg-?.?", "rg-1.2"));
+        assert!(!glob_match("*/rg", "a/b/rg"));
+        assert!(!glob_match("*/bin/rg", "ripgrep/bin/rg.1"));
+        assert!(!glob_match("bin/*", "bin"));
+        assert!(glob_match("**/bin/**/rg", "a/bin/b/bin/c/rg"));
+        assert!(!glob_match("**/bin/**/rg", "a/b/c/rg"));
+
+        // backtracking stays fast for many stars
+        let name = "a".repeat(100);
+        assert!(!glob_match(&format!("{}b", "*a".repeat(20)), &name));
+        let path = vec!["a"; 100].join("/");
+        assert!(!glob_match(&format!("{}b", "**/a/".repeat(20)), &path));
+    }
+
+    #[test]
+    fn unsafe_tar_entries_are_skipped() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+
+        let mut builder = tar::Builder::new(Vec::new());
+        for name in ["../escape", "tool"] {
+            let mut header = tar::Header::new_old();
+            header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+            header.set_size(7);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, &b"content"[..]).unwrap();
+        }
+        let tar = builder.into_inner().unwrap();
+
+        let unpack_dir = tmp_dir.path().join("unpacked");
+        fs::create_dir(&unpack_dir).unwrap();
+
+        let files = unpack_tar_stream(&tar[..], &unpack_dir).unwrap();
+
+        assert_eq!(files, vec!["tool"]);
+        assert!(!tmp_dir.path().join("escape").exists());
+    }
+
+    #[test]
+    #[cfg(target_family = "unix")]
+    fn executable_is_found_by_exe_path() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let source_dir = tmp_dir.path().join("source");
+        fs::create_dir_all(source_dir.join("dist/bin")).unwrap();
+        fs::create_dir_all(source_dir.join("dist/libexec")).unwrap();
+        for (path, mode) in [
+            ("dist/bin/tool-cli", 0o755),
+            ("dist/libexec/helper", 0o755),
+            ("dist/README.md", 0o644),
+        ] {
+            let path = source_dir.join(path);
+            fs::write(&path, "content").unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+        }
+
+        let archive_path = tmp_dir.path().join("tool-2.0.0-linux.tar");
+        let mut builder = tar::Builder::new(fs::File::create(&archive_path).unwrap());
+        builder
+            .append_dir_all("tool-release-2.0.0", &source_dir)
+            .unwrap();
+        builder.finish().unwrap();
+        drop(builder);
+
+        let unpack_dir = tmp_dir.path().join("unpacked");
+        fs::create_dir(&unpack_dir).unwrap();
+
+        let archive = Archive {
+            archive_path: &archive_path,
+            tmp_dir: &unpack_dir,
+            tag: "v2.0.0",
+            archive_type: ArchiveType::TarBall("tool-2.0.0-linux"),
+        };
+
+        let executables = vec![Executable::new("tool-cli")];
+        let paths = archive
+            .unpack(&executables, Some("tool-release-{version}/*/bin/{exe}"))
+            .ok()
+            .unwrap();
+        assert_eq!(
+            paths,
+            vec![unpack_dir.join("tool-release-2.0.0/dist/bin/tool-cli")]
+        );
+
+        match archive.unpack(&[Executable::new("tool")], None) {
+            Err(UnpackError::ExeNotFound { executables, .. }) => assert_eq!(
+                executables,
+                vec![
+                    "tool-release-2.0.0/dist/bin/tool-cli",
+                    "tool-release-2.0.0/dist/libexec/helper"
+                ]
+            ),
+            _ => panic!("a missing executable is found"),
+        }
+    }
+
+    #[test]
+    #[cfg(target_family = "unix")]
+    fn executable_is_searched_recursively() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let source_dir = tmp_dir.path().join("source");
+        for dir in ["nested/deep/bin", "docs/examples", "other/bin", "extra/bin"] {
+            fs::create_dir_all(source_dir.join(dir)).unwrap();
+        }
+        for (path, content, mode) in [
+            // the only 'tool' that is executable or a binary
+            ("nested/deep/bin/tool", "\x7fELF...", 0o644),
+            ("docs/examples/tool", "#!/bin/sh", 0o644),
+            // two executable copies of 'helper'
+            ("other/bin/helper", "#!/bin/sh", 0o755),
+            ("extra/bin/helper", "#!/bin/sh", 0o755),
+        ] {
+            let path = source_dir.join(path);
+            fs::write(&path, content).unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+        }
+
+        let archive_path = tmp_dir.path().join("tool.tar");
+        let mut builder = tar::Builder::new(fs::File::create(&archive_path).unwrap());
+        builder.append_dir_all("package", &source_dir).unwrap();
+        builder.finish().unwrap();
+        drop(builder);
+
+        let unpack_dir = tmp_dir.path().join("unpacked");
+        fs::create_dir(&unpack_dir).unwrap();
+
+        let archive = Archive {
+            archive_path: &archive_path,
+            tmp_dir: &unpack_dir,
+            tag: "v1.0.0",
+            archive_type: ArchiveType::TarBall("tool"),
+        };
+
+        let paths = archive
+            .unpack(&[Executable::new("tool")], None)
+            .ok()
+            .unwrap();
+        assert_eq!(paths, vec![unpack_dir.join("package/nested/deep/bin/tool")]);
+
+        match archive.unpack(&[Executable::new("helper")], None) {
+            Err(UnpackError::SeveralExesFound { candidates, .. }) => assert_eq!(
+                candidates,
+                vec!["package/extra/bin/helper", "package/other/bin/helper"]
+            ),
+            _ => panic!("one of several executables is picked"),
+        }
+    }
+
+    #[test]
+    fn archive_type_is_detected_from_content() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+
+        let tar_path = tmp_dir.path().join("tool.tar");
+        let mut builder = tar::Builder::new(fs::File::create(&tar_path).unwrap());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(4);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "tool", &b"tool"[..])
+            .unwrap();
+        builder.finish().unwrap();
+        drop(builder);
+
+        let tarball = fs::read(&tar_path).unwrap();
+
+        // old tarballs don't have the "ustar" magic
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_old();
+        header.as_old_mut().name[..4].copy_from_slice(b"tool");
+        header.set_size(4);
+        header.set_cksum();
+        builder.append(&header, &b"tool"[..]).unwrap();
+        let old_tarball = builder.into_inner().unwrap();
+
+        let samples: [(&str, Vec<u8>); 9] = [
+            ("tool.tar.gz", gzip(&tarball)),
+            ("tool-old.tar.gz", gzip(&old_tarball)),
+            ("tool-old.tgz", gzip(&old_tarball)),
+            // a deflate block of the reserved type
+            (
+                "tool-broken.gz",
+                b"\x1f\x8b\x08\0\0\0\0\0\0\xff\x07".to_vec(),
+            ),
+            ("tool-linux.gz", gzip(b"\x7fELF executable")),
+            ("tool-windows.pkg", b"PK\x03\x04".to_vec()),
+            ("tool.pkg", b"not an archive".to_vec()),
+            ("tool_1.0_amd64.deb", b"!<arch>\n".to_vec()),
+            ("tool-1.0.x86_64.rpm", vec![0xed, 0xab, 0xee, 0xdb]),
+        ];
+        for (name, content) in samples {
+            fs::write(tmp_dir.path().join(name), content).unwrap();
+        }
+
+        let archive_type = |name: &str| {
+            let path = tmp_dir.path().join(name);
+            Archive::from(&path, tmp_dir.path(), name, "v1.0.0")
+                .unwrap()
+                .map(|archive| match archive.archive_type {
+                    ArchiveType::TarBall(prefix) => format!("tarball {}", prefix),
+                    ArchiveType::Zip(prefix) => format!("zip {}", prefix),
+                    ArchiveType::Compressed(exe_file) => format!("compressed {}", exe_file),
+                    ArchiveType::Deb(prefix) => format!("deb {}", prefix),
+                    ArchiveType::Rpm(prefix) => format!("rpm {}", prefix),
+                    ArchiveType::AppImage(_) | ArchiveType::Exe(_) => String::from("binary"),
+                })
+        };
+
+        assert_eq!(archive_type("tool.tar"), Some(String::from("tarball tool")));
+        assert_eq!(
+            archive_type("tool.tar.gz"),
+            Some(String::from("tarball tool"))
+        );
+        assert_eq!(
+            archive_type("tool-old.tar.gz"),
+            Some(String::from("tarball tool-old"))
+        );
+        assert_eq!(
+            archive_type("tool-old.tgz"),
+            Some(String::from("tarball tool-old"))
+        );
+        assert!(
+            Archive::from(
+                &tmp_dir.path().join("tool-broken.gz"),
+                tmp_dir.path(),
+                "tool-broken.gz",
+                "v1.0.0"
+            )
+            .is_err()
+        );
+        assert_eq!(
+            archive_type("tool-linux.gz"),
+            Some(String::from("compressed tool-linux"))
+        );
+        assert_eq!(
+            archive_type("tool-windows.pkg"),
+            Some(String::from("zip tool-windows"))
+        );
+        assert_eq!(archive_type("tool.pkg"), None);
+        assert_eq!(
+            archive_type("tool_1.0_amd64.deb"),
+            Some(String::from("deb tool_1.0_amd64"))
+        );
+        assert_eq!(
+            archive_type("tool-1.0.x86_64.rpm"),
+            Some(String::from("rpm tool-1.0.x86_64"))
+        );
+    }
+
+    #[test]
+    fn compressed_executable_is_decompressed() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let asset_name = "rust-analyzer-x86_64-unknown-linux-gnu.gz";
+        let archive_path = tmp_dir.path().join(asset_name);
+        fs::write(&archive_path, gzip(b"\x7fELF rust-analyzer")).unwrap();
+
+        let archive = Archive::from(&archive_path, tmp_dir.path(), asset_name, "2024-01-01")
+            .unwrap()
+            .unwrap();
+        let paths = archive
+            .unpack(&[Executable::new("rust-analyzer")], None)
+            .ok()
+            .unwrap();
+
+        let exe_path = tmp_dir
+            .path()
+            .join("rust-analyzer-x86_64-unknown-linux-gnu");
+        assert_eq!(paths, vec![exe_path.clone()]);
+        assert_eq!(fs::read(exe_path).unwrap(), b"\x7fELF rust-analyzer");
+    }
+
+    #[test]
+    fn compressed_tarballs_are_unpacked() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(9);
+        header.set_mode(0o755);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "tool-1.0/bin/tool", &b"#!/bin/sh"[..])
+            .unwrap();
+        let tarball = builder.into_inner().unwrap();
+
+        let bzip2 = {
+            use std::io::Write;
+
+            let mut encoder =
+                bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::default());
+            encoder.write_all(&tarball).unwrap();
+            encoder.finish().unwrap()
+        };
+        let zstd = zstd::encode_all(&tarball[..], 0).unwrap();
+
+        for (asset_name, content) in [("tool-1.0.tar.bz2", bzip2), ("tool-1.0.tar.zst", zstd)] {
+            let unpack_dir = tmp_dir.path().join(asset_name.replace('.', "-"));
+            fs::create_dir(&unpack_dir).unwrap();
+            let archive_path = unpack_dir.join(asset_name);
+            fs::write(&archive_path, content).unwrap();
+
+            let archive = Archive::from(&archive_path, &unpack_dir, asset_name, "1.0")
+                .unwrap()
+                .unwrap();
+            let paths = archive
+                .unpack(&[Executable::new("tool")], None)
+                .ok()
+                .unwrap();
+
+            let exe_path = unpack_dir.join("tool-1.0/bin/tool");
+            assert_eq!(paths, vec![exe_path.clone()], "{}", asset_name);
+            assert_eq!(fs::read(exe_path).unwrap(), b"#!/bin/sh");
+        }
+    }
+}
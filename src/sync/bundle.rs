@@ -0,0 +1,588 @@
+//! Bundles with downloaded assets of all configured tools, used to install
+//! the tools on machines without network access.
+//!
+//! A bundle is a tar archive with the 'manifest.toml' file describing the
+//! bundled assets and the assets themselves stored in 'assets/<os>/<tool>/'.
+
+use console::{Emoji, style};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use tempfile::TempDir;
+
+use super::configure::configure_tool;
+use super::download::Downloader;
+use super::install::Installer;
+use super::progress::{SyncProgress, ToolPair};
+use super::state::InstallState;
+use super::{
+    WARNING, ensure_tool_store_directories, exe_paths, no_tools_message, remove_moved_executables,
+    shadowed_executables_warning, summary_message,
+};
+use crate::config::schema::Config;
+use crate::config::toml;
+use crate::infra::cache::{DownloadCache, sanitize};
+use crate::infra::client::Client;
+use crate::infra::err;
+use crate::infra::rate_limit::RateLimit;
+use crate::infra::retry::RetryPolicy;
+use crate::model::os::{OS, get_current_os};
+use crate::model::release::Asset;
+use crate::model::tool::{Executable, Extras, Tool, ToolAsset, ToolInfoTag};
+
+const MANIFEST_FILE: &str = "manifest.toml";
+const ASSETS_DIR: &str = "assets";
+
+const ERROR: Emoji<'_, '_> = Emoji("❌ ", "x ");
+const PACKAGE: Emoji<'_, '_> = Emoji("📦 ", "# ");
+
+/// Description of all assets stored in a bundle
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleManifest {
+    #[serde(default)]
+    pub assets: Vec<BundledAsset>,
+}
+
+/// Single asset of a tool for one OS stored in a bundle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundledAsset {
+    /// Name of the tool in the configuration
+    pub tool_name: String,
+
+    /// OS the asset is built for
+    pub os: String,
+
+    /// GitHub repository author
+    pub owner: String,
+
+    /// GitHub repository name
+    pub repo: String,
+
+    /// Git tag of the release
+    pub tag: String,
+
+    /// Executables to install from the asset
+    pub executables: Vec<Executable>,
+
+    /// Path of the executable inside the archive as a glob with '{exe}' and
+    /// '{version}' placeholders, e.g. "*/bin/{exe}"
+    #[serde(default)]
+    pub exe_path: Option<String>,
+
+    /// Man pages and completions inside the asset
+    #[serde(default)]
+    pub extras: Extras,
+
+    /// Whether the tool follows a moving tag
+    #[serde(default)]
+    pub is_channel: bool,
+
+    /// Id of the asset
+    pub asset_id: u32,
+
+    /// Name of the asset
+    pub asset_name: String,
+
+    /// Size of the asset in bytes
+    pub size: u64,
+
+    /// Upload time of the asset
+    pub updated_at: Option<String>,
+
+    /// Digest of the asset reported by GitHub
+    pub digest: Option<String>,
+}
+
+impl BundledAsset {
+    fn new(
+        tool_asset: &ToolAsset,
+        os: OS,
+    ) -> BundledAsset {
+        BundledAsset {
+            tool_name: tool_asset.tool_name.clone(),
+            os: os.to_string(),
+            owner: tool_asset.client.owner.clone(),
+            repo: tool_asset.client.repo.clone(),
+            tag: tool_asset.tag.clone(),
+            executables: tool_asset.executables.clone(),
+            exe_path: tool_asset.exe_path.clone(),
+            extras: tool_asset.extras.clone(),
+            is_channel: tool_asset.is_channel,
+            asset_id: tool_asset.asset.id,
+            asset_name: tool_asset.asset.name.clone(),
+            size: tool_asset.asset.size,
+            updated_at: tool_asset.asset.updated_at.clone(),
+            digest: tool_asset.asset.digest.clone(),
+        }
+    }
+
+    fn asset(&self) -> Asset {
+        Asset {
+            id: self.asset_id,
+            name: self.asset_name.clone(),
+            size: self.size,
+            updated_at: self.updated_at.clone(),
+            digest: self.digest.clone(),
+        }
+    }
+
+    /// Path of the asset inside the bundle
+    fn path(&self) -> PathBuf {
+        Path::new(ASSETS_DIR)
+            .join(sanitize(&self.os))
+            .join(sanitize(&self.tool_name))
+            .join(sanitize(&self.asset_name))
+    }
+
+    /// Tool asset to install without network access
+    fn to_tool_asset(&self) -> ToolAsset {
+        ToolAsset {
+            tool_name: self.tool_name.clone(),
+            tag: self.tag.clone(),
+            executables: self.executables.clone(),
+            exe_path: self.exe_path.clone(),
+            extras: self.extras.clone(),
+            asset: self.asset(),
+            is_channel: self.is_channel,
+            client: Client::offline(self.owner.clone(), self.repo.clone(), &self.tag),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum BundleError {
+    IO(std::io::Error),
+    Parse(::toml::de::Error),
+    Serialize(::toml::ser::Error),
+}
+
+impl Display for BundleError {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            BundleError::IO(e) => write!(f, "[IO Error] {}", e),
+            BundleError::Parse(e) => write!(f, "[Parsing Error] {}", e),
+            BundleError::Serialize(e) => write!(f, "[Serialize Error] {}", e),
+        }
+    }
+}
+
+/// Write the manifest and all the downloaded assets into a tar archive
+fn write_bundle(
+    output: &Path,
+    assets: &[(BundledAsset, PathBuf)],
+) -> Result<(), BundleError> {
+    let manifest = BundleManifest {
+        assets: assets.iter().map(|(asset, _)| asset.clone()).collect(),
+    };
+    let manifest = ::toml::to_string_pretty(&manifest).map_err(BundleError::Serialize)?;
+
+    let file = File::create(output).map_err(BundleError::IO)?;
+    let mut builder = tar::Builder::new(file);
+
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, MANIFEST_FILE, manifest.as_bytes())
+        .map_err(BundleError::IO)?;
+
+    for (asset, downloaded_path) in assets {
+        builder
+            .append_path_with_name(downloaded_path, asset.path())
+            .map_err(BundleError::IO)?;
+    }
+
+    builder.into_inner().map_err(BundleError::IO)?;
+    Ok(())
+}
+
+/// Unpack the bundle into 'dir' and read its manifest
+fn read_bundle(
+    bundle: &Path,
+    dir: &Path,
+) -> Result<BundleManifest, BundleError> {
+    let file = File::open(bundle).map_err(BundleError::IO)?;
+    tar::Archive::new(file)
+        .unpack(dir)
+        .map_err(BundleError::IO)?;
+
+    let manifest = fs::read_to_string(dir.join(MANIFEST_FILE)).map_err(BundleError::IO)?;
+    ::toml::from_str(&manifest).map_err(BundleError::Parse)
+}
+
+fn mk_tmp_dir() -> TempDir {
+    match TempDir::with_prefix("tool-sync-") {
+        Ok(tmp_dir) => tmp_dir,
+        Err(e) => err::abort_suggest_issue(format!("Error creating temporary directory: {}", e)),
+    }
+}
+
+/// Download assets of all the configured tools for each of the 'oses' and
+/// write them into the 'output' bundle
+pub fn bundle_from_path(
+    config_path: PathBuf,
+    output: PathBuf,
+    oses: Vec<OS>,
+    proxy: Option<String>,
+    wait_on_rate_limit: bool,
+) {
+    toml::with_parsed_file(config_path, proxy, wait_on_rate_limit, false, |config| {
+        create_bundle(config, output, oses)
+    });
+}
+
+fn create_bundle(
+    config: Config,
+    output: PathBuf,
+    mut oses: Vec<OS>,
+) {
+    if config.tools.is_empty() {
+        no_tools_message();
+        return;
+    }
+
+    if oses.is_empty() {
+        oses.push(get_current_os());
+    }
+
+    let mut unique_oses: Vec<OS> = Vec::new();
+    for os in oses {
+        if !unique_oses.contains(&os) {
+            unique_oses.push(os);
+        }
+    }
+
+    let retry = RetryPolicy::with_retries(config.retries);
+    let rate_limit = Rc::new(RateLimit::new(config.wait_on_rate_limit));
+    let download_cache = config.download_cache();
+
+    let mut tool_assets: Vec<(ToolAsset, OS)> = Vec::new();
+
+    for (tool_name, config_asset) in config.tools.iter() {
+        let tool_info = match configure_tool(tool_name, config_asset) {
+            Tool::Known(tool_info) => tool_info,
+            Tool::Error(e) => {
+                eprintln!("{} {} {}", ERROR, style(tool_name).cyan().bold(), e);
+                continue;
+            }
+        };
+
+        let client = Client {
+            owner: tool_info.owner.clone(),
+            repo: tool_info.repo.clone(),
+            version: tool_info.tag.to_str_version(),
+            proxy: config_asset.proxy.clone(),
+            retry,
+            rate_limit: Rc::clone(&rate_limit),
+        };
+
+        let release = match tool_info.tag {
+            ToolInfoTag::Prerelease => client.fetch_newest_release(),
+            _ => client.fetch_release_info(),
+        };
+
+        let release = match release {
+            Ok(release) => release,
+            Err(e) => {
+                eprintln!("{} {} {}", ERROR, style(tool_name).cyan().bold(), e);
+                continue;
+            }
+        };
+
+        for &os in &unique_oses {
+            match tool_info.select_asset_for_os(os, &release.assets) {
+                Err(e) => eprintln!(
+                    "{} {} ({}) {}",
+                    ERROR,
+                    style(tool_name).cyan().bold(),
+                    os,
+                    e
+                ),
+                Ok(asset) => tool_assets.push((
+                    ToolAsset {
+                        tool_name: tool_name.clone(),
+                        tag: release.tag_name.clone(),
+                        executables: tool_info.executables(),
+                        exe_path: tool_info.exe_path.clone(),
+                        extras: tool_info.extras.clone(),
+                        asset,
+                        is_channel: matches!(tool_info.tag, ToolInfoTag::Channel(_)),
+                        client: client.clone(),
+                    },
+                    os,
+                )),
+            }
+        }
+    }
+
+    if tool_assets.is_empty() {
+        err::abort_with("Nothing to bundle or encountered errors fetching all tools");
+    }
+
+    let labels: Vec<String> = tool_assets
+        .iter()
+        .map(|(tool_asset, os)| format!("{} ({})", tool_asset.tool_name, os))
+        .collect();
+    let tool_pairs = tool_assets
+        .iter()
+        .zip(&labels)
+        .map(|((tool_asset, _), label)| ToolPair {
+            name: label,
+            tag: &tool_asset.tag,
+        })
+        .collect();
+    let sync_progress = SyncProgress::new(tool_pairs);
+
+    let tmp_dir = mk_tmp_dir();
+    let mut bundled_assets = Vec::new();
+
+    for ((tool_asset, os), label) in tool_assets.iter().zip(&labels) {
+        let pb_msg = sync_progress.create_message_bar(label, &tool_asset.tag);
+        let bundled_asset = BundledAsset::new(tool_asset, *os);
+
+        let download_dir = tmp_dir.path().join(bundled_asset.path());
+        let download_dir = download_dir.parent().unwrap_or(tmp_dir.path());
+        if let Err(e) = fs::create_dir_all(download_dir) {
+            sync_progress.failure(pb_msg, label, &tool_asset.tag, format!("[error] {}", e));
+            continue;
+        }
+
+        let downloader = Downloader {
+            asset: &tool_asset.asset,
+            client: &tool_asset.client,
+            pb_msg: &pb_msg,
+            sync_progress: &sync_progress,
+            download_cache: download_cache.as_ref(),
+            offline: false,
+        };
+
+        match downloader.download(download_dir) {
+            Ok(download_info) => {
+                sync_progress.success(pb_msg, label, &tool_asset.tag);
+                bundled_assets.push((bundled_asset, download_info.archive_path));
+            }
+            Err(e) => {
+                sync_progress.failure(pb_msg, label, &tool_asset.tag, format!("[error] {}", e))
+            }
+        }
+    }
+
+    if let Err(e) = write_bundle(&output, &bundled_assets) {
+        err::abort_with(format!(
+            "Unable to write the bundle {}: {}",
+            output.display(),
+            e
+        ));
+    }
+
+    eprintln!(
+        "{} Bundled {} {} into {}",
+        PACKAGE,
+        bundled_assets.len(),
+        if bundled_assets.len() == 1 {
+            "asset"
+        } else {
+            "assets"
+        },
+        output.display()
+    );
+}
+
+/// Install all the tools for the current OS from the bundle
+pub fn import_from_path(
+    config_path: PathBuf,
+    bundle: PathBuf,
+    create_dirs: bool,
+) {
+    toml::with_parsed_file(config_path, None, false, create_dirs, |config| {
+        import_bundle(config, bundle)
+    });
+}
+
+fn import_bundle(
+    mut config: Config,
+    bundle: PathBuf,
+) {
+    let store_directory = config.ensure_store_directory();
+
+    let tmp_dir = mk_tmp_dir();
+    let manifest = match read_bundle(&bundle, tmp_dir.path()) {
+        Ok(manifest) => manifest,
+        Err(e) => err::abort_with(format!(
+            "Unable to read the bundle {}: {}",
+            bundle.display(),
+            e
+        )),
+    };
+
+    let os = get_current_os().to_string();
+    let (bundled_assets, unknown_assets): (Vec<&BundledAsset>, Vec<&BundledAsset>) = manifest
+        .assets
+        .iter()
+        .filter(|asset| asset.os == os)
+        .partition(|asset| config.tools.contains_key(&asset.tool_name));
+
+    for unknown_asset in &unknown_assets {
+        eprintln!(
+            "{} Skipping {}: the tool isn't in the configuration",
+            WARNING,
+            style(&unknown_asset.tool_name).cyan().bold()
+        );
+    }
+
+    if bundled_assets.is_empty() {
+        err::abort_with(format!(
+            "The bundle {} has no assets of the configured tools for {}",
+            bundle.display(),
+            os
+        ));
+    }
+
+    // tools whose store directory can't be used aren't installed
+    let tool_store_directories = ensure_tool_store_directories(&mut config, &store_directory);
+    let bundled_assets: Vec<&BundledAsset> = bundled_assets
+        .into_iter()
+        .filter(|asset| config.tools.contains_key(&asset.tool_name))
+        .collect();
+
+    // Assets are moved into a cache inside the unpacked bundle to install
+    // them from there, and copied into the user cache to make later offline
+    // syncs possible
+    let bundle_cache = DownloadCache::new(tmp_dir.path().join("cache"), u64::MAX);
+    let download_cache = config.download_cache();
+
+    for bundled_asset in &bundled_assets {
+        let asset = bundled_asset.asset();
+        let path = tmp_dir.path().join(bundled_asset.path());
+
+        if let Some(download_cache) = &download_cache {
+            let _ = download_cache.put(&asset, &path);
+        }
+
+        if let Err(e) = bundle_cache.put_moved(&asset, &path) {
+            eprintln!(
+                "{} Unable to unpack {} from the bundle: {}",
+                WARNING, asset.name, e
+            );
+        }
+    }
+
+    let tool_assets: Vec<ToolAsset> = bundled_assets
+        .iter()
+        .map(|bundled_asset| bundled_asset.to_tool_asset())
+        .collect();
+
+    let tool_pairs = tool_assets
+        .iter()
+        .map(|ta| ToolPair {
+            name: &ta.tool_name,
+            tag: &ta.tag,
+        })
+        .collect();
+
+    let sync_progress = SyncProgress::new(tool_pairs);
+    let installer = Installer::mk(
+        sync_progress,
+        Some(bundle_cache),
+        true,
+        config.versions_directory(),
+        config.extras_directories.expand(),
+    );
+
+    let mut install_state = InstallState::load();
+    let mut installed_tools: u64 = 0;
+    let mut installed_executables: Vec<(String, PathBuf)> = Vec::new();
+
+    for tool_asset in tool_assets {
+        let tool_store_directory = tool_store_directories
+            .get(&tool_asset.tool_name)
+            .unwrap_or(&store_directory);
+
+        if installer.install(&tool_asset, tool_store_directory) {
+            remove_moved_executables(&install_state, &tool_asset, tool_store_directory);
+            install_state.record(&tool_asset, tool_store_directory);
+            installed_tools += 1;
+            installed_executables.extend(exe_paths(&tool_asset, tool_store_directory));
+        }
+    }
+
+    if let Err(e) = install_state.save() {
+        eprintln!("{} Unable to save the install state: {}", WARNING, e);
+    }
+
+    summary_message(installed_tools, store_directory, &tool_store_directories);
+    shadowed_executables_warning(&installed_executables);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mk_bundled_asset(os: OS) -> BundledAsset {
+        BundledAsset {
+            tool_name: String::from("ripgrep"),
+            os: os.to_string(),
+            owner: String::from("BurntSushi"),
+            repo: String::from("ripgrep"),
+            tag: String::from("13.0.0"),
+            executables: vec![Executable::new("rg")],
+            exe_path: None,
+            extras: Extras::default(),
+            is_channel: false,
+            asset_id: 1,
+            asset_name: format!("ripgrep-13.0.0-{}.tar.gz", os),
+            size: 4,
+            updated_at: Some(String::from("2022-10-01T00:00:00Z")),
+            digest: None,
+        }
+    }
+
+    #[test]
+    fn bundled_asset_path() {
+        assert_eq!(
+            mk_bundled_asset(OS::Linux).path(),
+            Path::new("assets")
+                .join("linux")
+                .join("ripgrep")
+                .join("ripgrep-13.0.0-linux.tar.gz")
+        );
+    }
+
+    #[test]
+    fn bundle_roundtrip() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+
+        let linux_path = tmp_dir.path().join("linux.tar.gz");
+        let macos_path = tmp_dir.path().join("macos.tar.gz");
+        fs::write(&linux_path, "1234").unwrap();
+        fs::write(&macos_path, "5678").unwrap();
+
+        let assets = vec![
+            (mk_bundled_asset(OS::Linux), linux_path),
+            (mk_bundled_asset(OS::MacOS), macos_path),
+        ];
+
+        let bundle = tmp_dir.path().join("bundle.tar");
+        write_bundle(&bundle, &assets).unwrap();
+
+        let unpack_dir = tmp_dir.path().join("unpacked");
+        let manifest = read_bundle(&bundle, &unpack_dir).unwrap();
+
+        assert_eq!(
+            manifest,
+            BundleManifest {
+                assets: vec![mk_bundled_asset(OS::Linux), mk_bundled_asset(OS::MacOS)],
+            }
+        );
+        assert_eq!(
+            fs::read_to_string(unpack_dir.join(mk_bundled_asset(OS::MacOS).path())).unwrap(),
+            "5678"
+        );
+    }
+}
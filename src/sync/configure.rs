@@ -38,22 +38,22 @@ fn full_configure(config_asset: &ConfigAsset) -> Option<ToolInfo> {
         .exe_name
         .clone()
         .unwrap_or_else(|| repo.clone());
-    let tag = config_asset
-        .tag
-        .clone()
-        .map(ToolInfoTag::Specific)
-        .unwrap_or(ToolInfoTag::Latest);
+    let tag = configure_tag(config_asset);
 
     Some(ToolInfo {
         owner,
         repo,
         exe_name,
+        bin_name: config_asset.bin_name.clone(),
+        exe_names: config_asset.exe_names.clone(),
         asset_name: AssetName {
             linux: config_asset.asset_name.linux.clone(),
             macos: config_asset.asset_name.macos.clone(),
             windows: config_asset.asset_name.windows.clone(),
         },
         tag,
+        exe_path: config_asset.exe_path.clone(),
+        extras: config_asset.extras.clone(),
     })
 }
 
@@ -76,6 +76,15 @@ impl ToolInfo {
                 .exe_name
                 .clone()
                 .unwrap_or_else(|| self.exe_name.clone()),
+            bin_name: config_asset
+                .bin_name
+                .clone()
+                .or_else(|| self.bin_name.clone()),
+            exe_names: if config_asset.exe_names.is_empty() {
+                self.exe_names.clone()
+            } else {
+                config_asset.exe_names.clone()
+            },
             asset_name: AssetName {
                 linux: config_asset
                     .asset_name
@@ -93,19 +102,36 @@ impl ToolInfo {
                     .clone()
                     .or_else(|| self.asset_name.windows.clone()),
             },
-            tag: config_asset
-                .tag
+            tag: configure_tag(config_asset),
+            exe_path: config_asset
+                .exe_path
                 .clone()
-                .map(ToolInfoTag::Specific)
-                .unwrap_or(ToolInfoTag::Latest),
+                .or_else(|| self.exe_path.clone()),
+            extras: config_asset.extras.or(&self.extras),
         }
     }
 }
 
+/// Pick the release to download. An explicit 'tag' has the highest priority,
+/// then 'channel', then 'prerelease'.
+fn configure_tag(config_asset: &ConfigAsset) -> ToolInfoTag {
+    if let Some(tag) = &config_asset.tag {
+        ToolInfoTag::Specific(tag.clone())
+    } else if let Some(channel) = &config_asset.channel {
+        ToolInfoTag::Channel(channel.clone())
+    } else if config_asset.prerelease {
+        ToolInfoTag::Prerelease
+    } else {
+        ToolInfoTag::Latest
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::model::tool::{Completions, Executable, Extras};
+
     #[test]
     fn known_tool_with_empty_config_asset() {
         let tool_name = "ripgrep";
@@ -114,12 +140,19 @@ mod tests {
             owner: None,
             repo: None,
             exe_name: None,
+            bin_name: None,
+            exe_names: vec![],
             asset_name: AssetName {
                 linux: None,
                 macos: None,
                 windows: None,
             },
             tag: None,
+            prerelease: false,
+            channel: None,
+            store_directory: None,
+            exe_path: None,
+            extras: Extras::default(),
             proxy: None,
         };
 
@@ -137,12 +170,19 @@ mod tests {
             owner: None,
             repo: None,
             exe_name: None,
+            bin_name: None,
+            exe_names: vec![],
             asset_name: AssetName {
                 linux: None,
                 macos: None,
                 windows: None,
             },
             tag: None,
+            prerelease: false,
+            channel: None,
+            store_directory: None,
+            exe_path: None,
+            extras: Extras::default(),
             proxy: None,
         };
 
@@ -160,12 +200,19 @@ mod tests {
             owner: None,
             repo: None,
             exe_name: None,
+            bin_name: None,
+            exe_names: vec![],
             asset_name: AssetName {
                 linux: None,
                 macos: None,
                 windows: None,
             },
             tag: None,
+            prerelease: false,
+            channel: None,
+            store_directory: None,
+            exe_path: None,
+            extras: Extras::default(),
             proxy: None,
         };
 
@@ -185,12 +232,19 @@ mod tests {
             owner: Some(String::from("chshersh")),
             repo: None,
             exe_name: Some(String::from("abcdefu")),
+            bin_name: None,
+            exe_names: vec![],
             asset_name: AssetName {
                 linux: None,
                 macos: None,
                 windows: None,
             },
             tag: Some(String::from("1.2.3")),
+            prerelease: false,
+            channel: None,
+            store_directory: None,
+            exe_path: None,
+            extras: Extras::default(),
             proxy: None,
         };
 
@@ -208,12 +262,19 @@ mod tests {
             owner: Some(String::from("chshersh")),
             repo: Some(String::from("Pluto")),
             exe_name: Some(String::from("abcdefu")),
+            bin_name: None,
+            exe_names: vec![],
             asset_name: AssetName {
                 linux: Some(String::from("my-linux")),
                 macos: Some(String::from("my-macos")),
                 windows: Some(String::from("yours-windows")),
             },
             tag: Some(String::from("1.2.3")),
+            prerelease: false,
+            channel: None,
+            store_directory: None,
+            exe_path: None,
+            extras: Extras::default(),
             proxy: None,
         };
 
@@ -223,16 +284,56 @@ mod tests {
                 owner: "chshersh".to_string(),
                 repo: "Pluto".to_string(),
                 exe_name: "abcdefu".to_string(),
+                bin_name: None,
+                exe_names: vec![],
                 asset_name: AssetName {
                     linux: Some("my-linux".to_string()),
                     macos: Some("my-macos".to_string()),
                     windows: Some("yours-windows".to_string()),
                 },
                 tag: ToolInfoTag::Specific("1.2.3".to_string()),
+                exe_path: None,
+                extras: Extras::default(),
             })
         );
     }
 
+    #[test]
+    fn known_tool_with_bin_name() {
+        let tool_name = "difftastic";
+
+        let config_asset = ConfigAsset {
+            owner: None,
+            repo: None,
+            exe_name: None,
+            bin_name: Some(String::from("difftastic")),
+            exe_names: vec![],
+            asset_name: AssetName {
+                linux: None,
+                macos: None,
+                windows: None,
+            },
+            tag: None,
+            prerelease: false,
+            channel: None,
+            store_directory: None,
+            exe_path: None,
+            extras: Extras::default(),
+            proxy: None,
+        };
+
+        match configure_tool(tool_name, &config_asset) {
+            Tool::Known(tool_info) => assert_eq!(
+                tool_info.executables(),
+                vec![Executable {
+                    exe_name: String::from("difft"),
+                    bin_name: String::from("difftastic"),
+                }]
+            ),
+            Tool::Error(e) => panic!("Unexpected error: {}", e),
+        }
+    }
+
     #[test]
     fn empty_exe_name() {
         let tool_name = "abcdef";
@@ -241,12 +342,19 @@ mod tests {
             owner: Some(String::from("chshersh")),
             repo: Some(String::from("tool-sync")),
             exe_name: None,
+            bin_name: None,
+            exe_names: vec![],
             asset_name: AssetName {
                 linux: Some(String::from("my-linux")),
                 macos: Some(String::from("my-macos")),
                 windows: Some(String::from("yours-windows")),
             },
             tag: Some(String::from("1.0.0")),
+            prerelease: false,
+            channel: None,
+            store_directory: None,
+            exe_path: None,
+            extras: Extras::default(),
             proxy: None,
         };
 
@@ -256,12 +364,16 @@ mod tests {
                 owner: "chshersh".to_string(),
                 repo: "tool-sync".to_string(),
                 exe_name: "tool-sync".to_string(),
+                bin_name: None,
+                exe_names: vec![],
                 asset_name: AssetName {
                     linux: Some("my-linux".to_string()),
                     macos: Some("my-macos".to_string()),
                     windows: Some("yours-windows".to_string()),
                 },
                 tag: ToolInfoTag::Specific("1.0.0".to_string()),
+                exe_path: None,
+                extras: Extras::default(),
             })
         );
     }
@@ -274,12 +386,19 @@ mod tests {
             owner: Some(String::from("chshersh")),
             repo: None,
             exe_name: Some(String::from("abcdefu")),
+            bin_name: None,
+            exe_names: vec![],
             asset_name: AssetName {
                 linux: None,
                 macos: None,
                 windows: None,
             },
             tag: None,
+            prerelease: false,
+            channel: None,
+            store_directory: None,
+            exe_path: None,
+            extras: Extras::default(),
             proxy: None,
         };
 
@@ -289,12 +408,16 @@ mod tests {
                 owner: "chshersh".to_string(),
                 repo: "ripgrep".to_string(),
                 exe_name: "abcdefu".to_string(),
+                bin_name: None,
+                exe_names: vec![],
                 asset_name: AssetName {
                     linux: Some("x86_64-unknown-linux-musl".to_string()),
                     macos: Some("x86_64-apple-darwin".to_string()),
                     windows: Some("x86_64-pc-windows-msvc".to_string()),
                 },
                 tag: ToolInfoTag::Latest,
+                exe_path: None,
+                extras: lookup_tool(tool_name).unwrap().extras,
             })
         );
     }
@@ -307,12 +430,26 @@ mod tests {
             owner: Some(String::from("chshersh")),
             repo: Some(String::from("Pluto")),
             exe_name: Some(String::from("abcdefu")),
+            bin_name: None,
+            exe_names: vec![],
             asset_name: AssetName {
                 linux: Some(String::from("my-linux")),
                 macos: Some(String::from("my-macos")),
                 windows: Some(String::from("yours-windows")),
             },
             tag: Some(String::from("3.2.1")),
+            prerelease: false,
+            channel: None,
+            store_directory: None,
+            exe_path: None,
+            extras: Extras {
+                man_pages: vec![String::from("man/rg.1")],
+                completions: Completions {
+                    bash: Some(String::from("completions/rg.bash")),
+                    zsh: Some(String::from("completions/_rg")),
+                    fish: Some(String::from("completions/rg.fish")),
+                },
+            },
             proxy: None,
         };
 
@@ -322,13 +459,111 @@ mod tests {
                 owner: "chshersh".to_string(),
                 repo: "Pluto".to_string(),
                 exe_name: "abcdefu".to_string(),
+                bin_name: None,
+                exe_names: vec![],
                 asset_name: AssetName {
                     linux: Some("my-linux".to_string()),
                     macos: Some("my-macos".to_string()),
                     windows: Some("yours-windows".to_string()),
                 },
                 tag: ToolInfoTag::Specific("3.2.1".to_string()),
+                exe_path: None,
+                extras: config_asset.extras.clone(),
             })
         );
     }
+
+    #[test]
+    fn tag_has_priority_over_channel_and_prerelease() {
+        let tool_name = "ripgrep";
+
+        let config_asset = ConfigAsset {
+            owner: None,
+            repo: None,
+            exe_name: None,
+            bin_name: None,
+            exe_names: vec![],
+            asset_name: AssetName {
+                linux: None,
+                macos: None,
+                windows: None,
+            },
+            tag: Some(String::from("13.0.0")),
+            prerelease: true,
+            channel: Some(String::from("nightly")),
+            store_directory: None,
+            exe_path: None,
+            extras: Extras::default(),
+            proxy: None,
+        };
+
+        match configure_tool(tool_name, &config_asset) {
+            Tool::Known(tool_info) => {
+                assert_eq!(tool_info.tag, ToolInfoTag::Specific("13.0.0".to_string()))
+            }
+            Tool::Error(e) => panic!("Unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn channel_has_priority_over_prerelease() {
+        let tool_name = "ripgrep";
+
+        let config_asset = ConfigAsset {
+            owner: None,
+            repo: None,
+            exe_name: None,
+            bin_name: None,
+            exe_names: vec![],
+            asset_name: AssetName {
+                linux: None,
+                macos: None,
+                windows: None,
+            },
+            tag: None,
+            prerelease: true,
+            channel: Some(String::from("nightly")),
+            store_directory: None,
+            exe_path: None,
+            extras: Extras::default(),
+            proxy: None,
+        };
+
+        match configure_tool(tool_name, &config_asset) {
+            Tool::Known(tool_info) => {
+                assert_eq!(tool_info.tag, ToolInfoTag::Channel("nightly".to_string()))
+            }
+            Tool::Error(e) => panic!("Unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn prerelease_configuration() {
+        let tool_name = "ripgrep";
+
+        let config_asset = ConfigAsset {
+            owner: None,
+            repo: None,
+            exe_name: None,
+            bin_name: None,
+            exe_names: vec![],
+            asset_name: AssetName {
+                linux: None,
+                macos: None,
+                windows: None,
+            },
+            tag: None,
+            prerelease: true,
+            channel: None,
+            store_directory: None,
+            exe_path: None,
+            extras: Extras::default(),
+            proxy: None,
+        };
+
+        match configure_tool(tool_name, &config_asset) {
+            Tool::Known(tool_info) => assert_eq!(tool_info.tag, ToolInfoTag::Prerelease),
+            Tool::Error(e) => panic!("Unexpected error: {}", e),
+        }
+    }
 }
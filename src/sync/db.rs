@@ -1,7 +1,7 @@
 use std::collections::BTreeMap;
 
 use crate::model::asset_name::AssetName;
-use crate::model::tool::{ToolInfo, ToolInfoTag};
+use crate::model::tool::{Completions, Extras, ToolInfo, ToolInfoTag};
 
 const NOT_SUPPORTED: &str = "NOT_SUPPORTED";
 
@@ -24,6 +24,12 @@ pub fn build_db() -> BTreeMap<String, ToolInfo> {
             macos: "x86_64-apple-darwin",
             windows: "x86_64-pc-windows-msvc",
             tag: ToolInfoTag::Latest,
+            extras: StaticExtras {
+                man_pages: &["bat.1"],
+                bash: Some("autocomplete/bat.bash"),
+                zsh: Some("autocomplete/bat.zsh"),
+                fish: Some("autocomplete/bat.fish"),
+            },
         },
     );
     tools.insert(
@@ -36,6 +42,7 @@ pub fn build_db() -> BTreeMap<String, ToolInfo> {
             macos: "x86_64-apple-darwin",
             windows: "x86_64-pc-windows-msvc",
             tag: ToolInfoTag::Latest,
+            extras: NO_EXTRAS,
         },
     );
     tools.insert(
@@ -48,6 +55,12 @@ pub fn build_db() -> BTreeMap<String, ToolInfo> {
             macos: "macos-x86_64",
             windows: NOT_SUPPORTED,
             tag: ToolInfoTag::Latest,
+            extras: StaticExtras {
+                man_pages: &["man/exa.1"],
+                bash: Some("completions/exa.bash"),
+                zsh: Some("completions/exa.zsh"),
+                fish: Some("completions/exa.fish"),
+            },
         },
     );
     tools.insert(
@@ -60,6 +73,12 @@ pub fn build_db() -> BTreeMap<String, ToolInfo> {
             macos: "x86_64-apple-darwin",
             windows: "x86_64-pc-windows-msvc",
             tag: ToolInfoTag::Latest,
+            extras: StaticExtras {
+                man_pages: &["fd.1"],
+                bash: Some("autocomplete/fd.bash"),
+                zsh: Some("autocomplete/_fd"),
+                fish: Some("autocomplete/fd.fish"),
+            },
         },
     );
     tools.insert(
@@ -72,6 +91,12 @@ pub fn build_db() -> BTreeMap<String, ToolInfo> {
             macos: "x86_64-apple-darwin",
             windows: "x86_64-pc-windows-msvc",
             tag: ToolInfoTag::Latest,
+            extras: StaticExtras {
+                man_pages: &["hyperfine.1"],
+                bash: Some("autocomplete/hyperfine.bash"),
+                zsh: Some("autocomplete/_hyperfine"),
+                fish: Some("autocomplete/hyperfine.fish"),
+            },
         },
     );
     tools.insert(
@@ -84,6 +109,12 @@ pub fn build_db() -> BTreeMap<String, ToolInfo> {
             macos: "x86_64-apple-darwin",
             windows: "x86_64-pc-windows-msvc",
             tag: ToolInfoTag::Latest,
+            extras: StaticExtras {
+                man_pages: &["just.1"],
+                bash: Some("completions/just.bash"),
+                zsh: Some("completions/just.zsh"),
+                fish: Some("completions/just.fish"),
+            },
         },
     );
     tools.insert(
@@ -96,6 +127,7 @@ pub fn build_db() -> BTreeMap<String, ToolInfo> {
             macos: "Darwin_amd64",
             windows: "Windows_amd64",
             tag: ToolInfoTag::Latest,
+            extras: NO_EXTRAS,
         },
     );
     tools.insert(
@@ -108,6 +140,7 @@ pub fn build_db() -> BTreeMap<String, ToolInfo> {
             macos: "x86_64-mac",
             windows: "x86_64-windows.zip",
             tag: ToolInfoTag::Latest,
+            extras: NO_EXTRAS,
         },
     );
     tools.insert(
@@ -120,6 +153,12 @@ pub fn build_db() -> BTreeMap<String, ToolInfo> {
             macos: "x86_64-apple-darwin",
             windows: "x86_64-pc-windows-msvc",
             tag: ToolInfoTag::Latest,
+            extras: StaticExtras {
+                man_pages: &["doc/rg.1"],
+                bash: Some("complete/rg.bash"),
+                zsh: Some("complete/_rg"),
+                fish: Some("complete/rg.fish"),
+            },
         },
     );
     tools.insert(
@@ -132,6 +171,7 @@ pub fn build_db() -> BTreeMap<String, ToolInfo> {
             macos: "x86_64-apple-darwin",
             windows: "x86_64-pc-windows-msvc",
             tag: ToolInfoTag::Latest,
+            extras: NO_EXTRAS,
         },
     );
     tools.insert(
@@ -144,6 +184,7 @@ pub fn build_db() -> BTreeMap<String, ToolInfo> {
             macos: "x86_64-apple-darwin",
             windows: "x86_64-pc-windows-msvc.zip",
             tag: ToolInfoTag::Latest,
+            extras: NO_EXTRAS,
         },
     );
     tools.insert(
@@ -156,6 +197,7 @@ pub fn build_db() -> BTreeMap<String, ToolInfo> {
             macos: "x86_64-apple-darwin.tar.gz",
             windows: "x86_64-pc-windows-msvc.zip",
             tag: ToolInfoTag::Latest,
+            extras: NO_EXTRAS,
         },
     );
     tools.insert(
@@ -168,6 +210,7 @@ pub fn build_db() -> BTreeMap<String, ToolInfo> {
             macos: "macOS_amd64",
             windows: "windows_amd64.zip",
             tag: ToolInfoTag::Latest,
+            extras: NO_EXTRAS,
         },
     );
     //tools.insert(
@@ -230,23 +273,61 @@ struct StaticToolInfo {
     /// Version tag
     pub tag: ToolInfoTag,
 
+    /// Man pages and completions inside the asset
+    pub extras: StaticExtras,
+
     pub linux: &'static str,
     pub macos: &'static str,
     pub windows: &'static str,
 }
 
+struct StaticExtras {
+    pub man_pages: &'static [&'static str],
+    pub bash: Option<&'static str>,
+    pub zsh: Option<&'static str>,
+    pub fish: Option<&'static str>,
+}
+
+const NO_EXTRAS: StaticExtras = StaticExtras {
+    man_pages: &[],
+    bash: None,
+    zsh: None,
+    fish: None,
+};
+
+impl From<StaticExtras> for Extras {
+    fn from(static_extras: StaticExtras) -> Self {
+        Extras {
+            man_pages: static_extras
+                .man_pages
+                .iter()
+                .map(|man_page| man_page.to_string())
+                .collect(),
+            completions: Completions {
+                bash: static_extras.bash.map(String::from),
+                zsh: static_extras.zsh.map(String::from),
+                fish: static_extras.fish.map(String::from),
+            },
+        }
+    }
+}
+
 impl From<StaticToolInfo> for ToolInfo {
     fn from(static_tool_info: StaticToolInfo) -> Self {
         ToolInfo {
             owner: static_tool_info.owner.to_string(),
             repo: static_tool_info.repo.to_string(),
             exe_name: static_tool_info.exe_name.to_string(),
+            bin_name: None,
+            exe_names: vec![],
             asset_name: AssetName {
                 linux: from_supported_asset(static_tool_info.linux),
                 macos: from_supported_asset(static_tool_info.macos),
                 windows: from_supported_asset(static_tool_info.windows),
             },
             tag: static_tool_info.tag,
+            exe_path: None,
+            extras: static_tool_info.extras.into(),
         }
     }
 }
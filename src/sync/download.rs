@@ -1,10 +1,13 @@
 use indicatif::ProgressBar;
 use std::error::Error;
-use std::fs::File;
-use std::io::Write;
+use std::fmt::{Display, Formatter};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 
+use crate::infra::cache::{self, DownloadCache};
 use crate::infra::client::Client;
+use crate::infra::rate_limit::RateLimitExceeded;
 use crate::model::release::Asset;
 use crate::sync::progress::SyncProgress;
 
@@ -13,6 +16,10 @@ pub struct Downloader<'a> {
     pub client: &'a Client,
     pub pb_msg: &'a ProgressBar,
     pub sync_progress: &'a SyncProgress,
+    pub download_cache: Option<&'a DownloadCache>,
+
+    /// Fail instead of downloading assets missing in the cache
+    pub offline: bool,
 }
 
 /// Info about the downloaded asset
@@ -20,44 +27,361 @@ pub struct DownloadInfo {
     pub archive_path: PathBuf,
 }
 
+#[derive(Debug)]
+pub enum DownloadError {
+    /// Request for the asset failed
+    Request(Box<dyn Error>),
+
+    /// Connection was interrupted while reading the asset
+    Read(std::io::Error),
+
+    /// Writing the downloaded bytes on disk failed
+    Write(std::io::Error),
+
+    /// The number of downloaded bytes doesn't match the asset size
+    SizeMismatch { expected: u64, downloaded: u64 },
+
+    /// The downloaded bytes don't match the asset digest
+    DigestMismatch { expected: String },
+
+    /// The asset isn't cached and downloading is disabled
+    NotAvailableOffline,
+}
+
+impl DownloadError {
+    /// Whether the download may succeed if retried
+    fn is_transient(&self) -> bool {
+        match self {
+            // the client already retried transient request errors
+            DownloadError::Request(_) => false,
+            DownloadError::Read(_) => true,
+            DownloadError::Write(_) | DownloadError::NotAvailableOffline => false,
+
+            // the connection was closed too early, the rest can be downloaded
+            DownloadError::SizeMismatch {
+                expected,
+                downloaded,
+            } => downloaded < expected,
+
+            // the whole asset was downloaded but it's corrupted
+            DownloadError::DigestMismatch { .. } => false,
+        }
+    }
+
+    /// Whether the download failed because of network problems rather than
+    /// problems on this machine
+    pub fn is_network_error(&self) -> bool {
+        self.rate_limit_exceeded().is_none()
+            && !matches!(
+                self,
+                DownloadError::Write(_) | DownloadError::NotAvailableOffline
+            )
+    }
+
+    /// The rate limit error if the request wasn't sent because of it
+    pub fn rate_limit_exceeded(&self) -> Option<&RateLimitExceeded> {
+        match self {
+            DownloadError::Request(e) => e.downcast_ref::<RateLimitExceeded>(),
+            _ => None,
+        }
+    }
+}
+
+impl Display for DownloadError {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            DownloadError::Request(e) => write!(f, "{}", e),
+            DownloadError::Read(e) => write!(f, "Download interrupted: {}", e),
+            DownloadError::Write(e) => write!(f, "Can't save the downloaded asset: {}", e),
+            DownloadError::SizeMismatch {
+                expected,
+                downloaded,
+            } => write!(
+                f,
+                "Downloaded {} bytes but the asset size is {} bytes",
+                downloaded, expected
+            ),
+            DownloadError::DigestMismatch { expected } => {
+                write!(
+                    f,
+                    "The downloaded asset doesn't match its digest {}",
+                    expected
+                )
+            }
+            DownloadError::NotAvailableOffline => {
+                write!(f, "The asset is not in the download cache")
+            }
+        }
+    }
+}
+
+impl Error for DownloadError {}
+
 impl Downloader<'_> {
     fn download_asset(
         &self,
         tmp_dir: &Path,
-    ) -> Result<PathBuf, Box<dyn Error>> {
-        let mut stream = self.client.get_asset_stream(self.asset)?;
-
+    ) -> Result<PathBuf, DownloadError> {
         let download_path = tmp_dir.join(&self.asset.name);
-        let mut destination = File::create(&download_path)?;
+        let mut destination = File::create(&download_path).map_err(DownloadError::Write)?;
 
         self.pb_msg.set_message("Downloading...");
         let pb_downloading = self.sync_progress.create_progress_bar(self.asset.size);
 
+        let retry_policy = self.client.retry;
+        let mut downloaded: u64 = 0;
+        let mut retry = 0;
+
+        // Resume the download from the last written byte on transient errors.
+        // If all retries fail, the error is returned so a partially
+        // downloaded asset is never unpacked.
+        loop {
+            match self.download_from(&mut destination, &mut downloaded, &pb_downloading) {
+                Ok(()) => break,
+                Err(e) if retry < retry_policy.max_retries && e.is_transient() => {
+                    retry += 1;
+                    self.pb_msg.set_message(format!(
+                        "Retrying download ({}/{})...",
+                        retry, retry_policy.max_retries
+                    ));
+                    retry_policy.wait(retry);
+                }
+                Err(e) => {
+                    SyncProgress::finish_progress(pb_downloading);
+                    return Err(e);
+                }
+            }
+        }
+
+        self.pb_msg.set_message("Downloaded!");
+        SyncProgress::finish_progress(pb_downloading);
+
+        Ok(download_path)
+    }
+
+    /// Stream the asset into `destination` starting from `downloaded` bytes
+    fn download_from(
+        &self,
+        destination: &mut File,
+        downloaded: &mut u64,
+        pb_downloading: &ProgressBar,
+    ) -> Result<(), DownloadError> {
+        let mut stream = self
+            .client
+            .get_asset_stream(self.asset, *downloaded)
+            .map_err(DownloadError::Request)?;
+
+        // The server can't resume the download, so start from scratch
+        if stream.offset != *downloaded {
+            destination.set_len(0).map_err(DownloadError::Write)?;
+            *downloaded = 0;
+        }
+
+        destination
+            .seek(SeekFrom::Start(*downloaded))
+            .map_err(DownloadError::Write)?;
+        pb_downloading.set_position(*downloaded);
+
         let mut buffer = [0; 4096];
-        while let Ok(bytes_read) = stream.read(&mut buffer) {
+        loop {
+            let bytes_read = stream
+                .reader
+                .read(&mut buffer)
+                .map_err(DownloadError::Read)?;
             if bytes_read == 0 {
-                break;
+                return self.check_size(*downloaded);
             }
 
+            destination
+                .write_all(&buffer[..bytes_read])
+                .map_err(DownloadError::Write)?;
+
+            *downloaded += bytes_read as u64;
             pb_downloading.inc(bytes_read as u64);
-            destination.write_all(&buffer[..bytes_read])?;
         }
+    }
 
-        self.pb_msg.set_message("Downloaded!");
-        SyncProgress::finish_progress(pb_downloading);
+    fn check_size(
+        &self,
+        downloaded: u64,
+    ) -> Result<(), DownloadError> {
+        if downloaded == self.asset.size {
+            Ok(())
+        } else {
+            Err(DownloadError::SizeMismatch {
+                expected: self.asset.size,
+                downloaded,
+            })
+        }
+    }
 
-        Ok(download_path)
+    /// Copy the asset from the download cache if it's there
+    fn copy_from_cache(
+        &self,
+        tmp_dir: &Path,
+    ) -> Option<PathBuf> {
+        let cached_path = self.download_cache?.get(self.asset)?;
+        let archive_path = tmp_dir.join(&self.asset.name);
+
+        self.pb_msg.set_message("Copying from cache...");
+        fs::copy(cached_path, &archive_path).ok()?;
+
+        Some(archive_path)
     }
 
     /// Download an asset and return a path of the downloaded artefact
     pub fn download(
         &self,
         tmp_dir: &Path,
-    ) -> Result<DownloadInfo, Box<dyn Error>> {
+    ) -> Result<DownloadInfo, DownloadError> {
         self.pb_msg.set_message("Fetching info...");
 
+        if let Some(archive_path) = self.copy_from_cache(tmp_dir) {
+            return Ok(DownloadInfo { archive_path });
+        }
+
+        if self.offline {
+            return Err(DownloadError::NotAvailableOffline);
+        }
+
         let archive_path = self.download_asset(tmp_dir)?;
+        check_digest(self.asset, &archive_path)?;
+
+        if let Some(download_cache) = self.download_cache {
+            // the cache is only an optimisation so failing to write it is fine
+            let _ = download_cache.put(self.asset, &archive_path);
+        }
 
         Ok(DownloadInfo { archive_path })
     }
 }
+
+/// Fail if the downloaded asset doesn't match the digest reported by GitHub
+fn check_digest(
+    asset: &Asset,
+    path: &Path,
+) -> Result<(), DownloadError> {
+    match cache::matches_digest(path, asset.digest.as_deref()) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(DownloadError::DigestMismatch {
+            expected: asset.digest.clone().unwrap_or_default(),
+        }),
+        Err(e) => Err(DownloadError::Write(e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// sha256 digest of "1234"
+    const SHA256_1234: &str =
+        "sha256:03ac674216f3e15c761ee1a5e255f067953623c8b388b4459e13f978d7c846f4";
+
+    fn mk_asset(digest: &str) -> Asset {
+        Asset {
+            id: 1,
+            name: String::from("tool.tar.gz"),
+            size: 4,
+            updated_at: None,
+            digest: Some(String::from(digest)),
+        }
+    }
+
+    #[test]
+    fn download_matching_digest_is_accepted() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let path = tmp_dir.path().join("tool.tar.gz");
+        fs::write(&path, "1234").unwrap();
+
+        assert!(check_digest(&mk_asset(SHA256_1234), &path).is_ok());
+    }
+
+    #[test]
+    fn download_with_wrong_digest_is_rejected() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let path = tmp_dir.path().join("tool.tar.gz");
+        // same size as the asset but different bytes
+        fs::write(&path, "4321").unwrap();
+
+        let err = check_digest(&mk_asset(SHA256_1234), &path).unwrap_err();
+
+        assert!(matches!(err, DownloadError::DigestMismatch { .. }));
+        assert!(!err.is_transient());
+        assert!(err.is_network_error());
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "The downloaded asset doesn't match its digest {}",
+                SHA256_1234
+            )
+        );
+    }
+
+    #[test]
+    fn truncated_download_is_retried() {
+        let err = DownloadError::SizeMismatch {
+            expected: 100,
+            downloaded: 42,
+        };
+
+        assert!(err.is_transient());
+        assert!(err.is_network_error());
+        assert_eq!(
+            err.to_string(),
+            "Downloaded 42 bytes but the asset size is 100 bytes"
+        );
+    }
+
+    #[test]
+    fn oversized_download_is_not_retried() {
+        let err = DownloadError::SizeMismatch {
+            expected: 100,
+            downloaded: 142,
+        };
+
+        assert!(!err.is_transient());
+    }
+
+    #[test]
+    fn request_error_is_not_retried_again() {
+        let err = DownloadError::Request(Box::new(ureq::Error::Status(
+            503,
+            ureq::Response::new(503, "Service Unavailable", "").unwrap(),
+        )));
+
+        assert!(!err.is_transient());
+        assert!(err.is_network_error());
+    }
+
+    #[test]
+    fn rate_limit_error_is_not_a_network_error() {
+        let err = DownloadError::Request(Box::new(RateLimitExceeded { reset: 0 }));
+
+        assert!(!err.is_transient());
+        assert!(!err.is_network_error());
+        assert_eq!(
+            err.rate_limit_exceeded(),
+            Some(&RateLimitExceeded { reset: 0 })
+        );
+    }
+
+    #[test]
+    fn write_error_is_not_a_network_error() {
+        let err = DownloadError::Write(std::io::Error::other("disk full"));
+
+        assert!(!err.is_transient());
+        assert!(!err.is_network_error());
+    }
+
+    #[test]
+    fn offline_error_is_not_a_network_error() {
+        let err = DownloadError::NotAvailableOffline;
+
+        assert!(!err.is_transient());
+        assert!(!err.is_network_error());
+    }
+}
@@ -1,4 +1,5 @@
 use indicatif::ProgressBar;
+use std::cell::Cell;
 use std::error::Error;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -7,26 +8,44 @@ use tempfile::TempDir;
 #[cfg(target_family = "unix")]
 use std::os::unix::fs::PermissionsExt;
 
+use crate::config::schema::ExtrasDirectories;
 use crate::err;
+use crate::infra::cache::DownloadCache;
 use crate::model::asset_name::mk_exe_name;
 use crate::model::tool::ToolAsset;
 
+use super::ERROR;
 use super::archive::Archive;
-use super::download::Downloader;
+use super::download::{DownloadError, Downloader};
 use super::progress::SyncProgress;
+use super::state::version_directory;
 
-pub struct Installer<'a> {
-    store_directory: &'a Path,
+pub struct Installer {
     tmp_dir: TempDir,
     sync_progress: SyncProgress,
+    download_cache: Option<DownloadCache>,
+    offline: bool,
+
+    /// Unpack each version into its own directory under this one and link
+    /// executables from 'store_directory' instead of copying them there
+    versions_directory: Option<PathBuf>,
+
+    /// Where to install man pages and completions
+    extras_directories: ExtrasDirectories<PathBuf>,
+
+    /// The rate limit hint is printed only for the first failed tool
+    is_rate_limit_reported: Cell<bool>,
 }
 
-impl Installer<'_> {
+impl Installer {
     /// This functions panics when it can't create a temporary directory
     /// (e.g. not enough disk space?)
     pub fn mk(
-        store_directory: &Path,
         sync_progress: SyncProgress,
+        download_cache: Option<DownloadCache>,
+        offline: bool,
+        versions_directory: Option<PathBuf>,
+        extras_directories: ExtrasDirectories<PathBuf>,
     ) -> Installer {
         let tmp_dir = TempDir::with_prefix("tool-sync-");
         match tmp_dir {
@@ -34,46 +53,100 @@ impl Installer<'_> {
                 err::abort_suggest_issue(format!("Error creating temporary directory: {}", e));
             }
             Ok(tmp_dir) => Installer {
-                store_directory,
                 tmp_dir,
                 sync_progress,
+                download_cache,
+                offline,
+                versions_directory,
+                extras_directories,
+                is_rate_limit_reported: Cell::new(false),
             },
         }
     }
 
-    /// Returns `true` if the installation was successful
+    /// Report that the tool doesn't need to be installed again
+    pub fn up_to_date(
+        &self,
+        tool_asset: &ToolAsset,
+    ) {
+        let tool_name = &tool_asset.tool_name;
+        let tag = &tool_asset.tag;
+
+        let pb_msg = self.sync_progress.create_message_bar(tool_name, tag);
+        self.sync_progress.up_to_date(pb_msg, tool_name, tag);
+    }
+
+    /// Install the tool into 'store_directory'. Returns `true` if the
+    /// installation was successful
     pub fn install(
         &self,
-        tool_asset: ToolAsset,
+        tool_asset: &ToolAsset,
+        store_directory: &Path,
     ) -> bool {
         let tool_name = &tool_asset.tool_name;
         let tag = &tool_asset.tag;
 
         let pb_msg = self.sync_progress.create_message_bar(tool_name, tag);
 
-        match self.sync_single_tool(&tool_asset, &pb_msg) {
-            Ok(_) => {
+        match self.sync_single_tool(tool_asset, store_directory, &pb_msg) {
+            Ok(extras_errors) if extras_errors.is_empty() => {
                 self.sync_progress.success(pb_msg, tool_name, tag);
                 true
             }
-            Err(e) => {
+            // the executables are already replaced, so the tool counts as installed
+            Ok(extras_errors) => {
+                let warning_msg = format!(
+                    "[warning] Unable to install man pages or completions: {}",
+                    extras_errors.join("; ")
+                );
                 self.sync_progress
-                    .failure(pb_msg, tool_name, tag, format!("[error] {}", e));
+                    .success_with_warning(pb_msg, tool_name, tag, warning_msg);
+                true
+            }
+            Err(e) => {
+                let download_err = e.downcast_ref::<DownloadError>();
+
+                if let Some(rate_limit_err) = download_err.and_then(|e| e.rate_limit_exceeded())
+                    && !self.is_rate_limit_reported.replace(true)
+                {
+                    self.sync_progress
+                        .println(format!("{} {}", ERROR, rate_limit_err));
+                }
+
+                let err_msg = match download_err {
+                    Some(download_err) if download_err.rate_limit_exceeded().is_some() => {
+                        "[rate limit exceeded] Download skipped".to_string()
+                    }
+                    Some(download_err) if download_err.is_network_error() => {
+                        format!("[network error] {}", download_err)
+                    }
+                    Some(DownloadError::NotAvailableOffline) => {
+                        format!("[not available offline] {}", e)
+                    }
+                    _ => format!("[error] {}", e),
+                };
+
+                self.sync_progress.failure(pb_msg, tool_name, tag, err_msg);
                 false
             }
         }
     }
 
+    /// Install executables of the tool and return errors of installing its
+    /// extras, which don't fail the installation
     fn sync_single_tool(
         &self,
         tool_asset: &ToolAsset,
+        store_directory: &Path,
         pb_msg: &ProgressBar,
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<Vec<String>, Box<dyn Error>> {
         let downloader = Downloader {
             asset: &tool_asset.asset,
             client: &tool_asset.client,
             sync_progress: &self.sync_progress,
             pb_msg,
+            download_cache: self.download_cache.as_ref(),
+            offline: self.offline,
         };
 
         let download_info = downloader.download(self.tmp_dir.path())?;
@@ -81,27 +154,122 @@ impl Installer<'_> {
         let archive = Archive::from(
             &download_info.archive_path,
             self.tmp_dir.path(),
-            &tool_asset.exe_name,
             &tool_asset.asset.name,
             &tool_asset.tag,
-        );
+        )
+        .map_err(|e| format!("Unable to read the asset {}: {}", tool_asset.asset.name, e))?;
 
         match archive {
             None => Err(format!("Unsupported asset type: {}", tool_asset.asset.name).into()),
-            Some(archive) => match archive.unpack() {
-                Err(unpack_err) => {
-                    println!("{:?}", &tool_asset);
-                    Err(unpack_err.to_string().into())
+            Some(archive) => {
+                match archive.unpack(&tool_asset.executables, tool_asset.exe_path.as_deref()) {
+                    Err(unpack_err) => {
+                        println!("{:?}", &tool_asset);
+                        Err(unpack_err.to_string().into())
+                    }
+                    Ok(tool_paths) => {
+                        self.install_executables(tool_asset, tool_paths, store_directory)?;
+                        Ok(self.install_extras(tool_asset, &archive))
+                    }
                 }
-                Ok(tool_path) => {
-                    copy_file(tool_path, self.store_directory, &tool_asset.exe_name)?;
-                    Ok(())
+            }
+        }
+    }
+
+    /// Copy man pages and completions found in the archive into configured
+    /// directories and return the errors of copying. Extras missing from the
+    /// archive are skipped because their paths often change between versions.
+    fn install_extras(
+        &self,
+        tool_asset: &ToolAsset,
+        archive: &Archive,
+    ) -> Vec<String> {
+        let extras = &tool_asset.extras;
+        let directories = &self.extras_directories;
+        let mut errors = Vec::new();
+
+        if let Some(man_directory) = &directories.man {
+            for man_page in &extras.man_pages {
+                if let Some(path) = archive.find_extra(man_page)
+                    && let Some(file_name) = path.file_name().and_then(|name| name.to_str())
+                {
+                    let section_directory =
+                        man_directory.join(format!("man{}", man_section(file_name)));
+                    if let Err(e) = copy_extra(&path, &section_directory, file_name) {
+                        errors.push(format!("{}: {}", section_directory.display(), e));
+                    }
                 }
-            },
+            }
         }
+
+        // each shell finds completions by the name of the command
+        let bin_name = tool_asset.exe_name();
+        let completions = [
+            (
+                &directories.bash,
+                &extras.completions.bash,
+                bin_name.to_owned(),
+            ),
+            (
+                &directories.zsh,
+                &extras.completions.zsh,
+                format!("_{}", bin_name),
+            ),
+            (
+                &directories.fish,
+                &extras.completions.fish,
+                format!("{}.fish", bin_name),
+            ),
+        ];
+
+        for (directory, completion, file_name) in completions {
+            if let (Some(directory), Some(completion)) = (directory, completion)
+                && let Some(path) = archive.find_extra(completion)
+                && let Err(e) = copy_extra(&path, directory, &file_name)
+            {
+                errors.push(format!("{}: {}", directory.display(), e));
+            }
+        }
+
+        errors
+    }
+
+    fn install_executables(
+        &self,
+        tool_asset: &ToolAsset,
+        tool_paths: Vec<PathBuf>,
+        store_directory: &Path,
+    ) -> std::io::Result<()> {
+        let executables = tool_asset.executables.iter().zip(tool_paths);
+
+        match &self.versions_directory {
+            None => {
+                for (exe, tool_path) in executables {
+                    copy_file(tool_path, store_directory, &exe.bin_name)?;
+                }
+            }
+            Some(versions_directory) => {
+                let version_directory =
+                    version_directory(versions_directory, &tool_asset.tool_name, &tool_asset.tag);
+
+                // links are switched only when all executables are in place
+                fs::create_dir_all(&version_directory)?;
+                for (exe, tool_path) in executables {
+                    copy_file(tool_path, &version_directory, &exe.bin_name)?;
+                }
+                for exe in &tool_asset.executables {
+                    link_file(&version_directory, store_directory, &exe.bin_name)?;
+                }
+            }
+        }
+
+        Ok(())
     }
 }
 
+/// Install the executable into 'store_directory'. The file is copied next to
+/// the old executable first and then renamed over it, so an interrupted sync
+/// never leaves a partially written or missing executable.
 fn copy_file(
     tool_path: PathBuf,
     store_directory: &Path,
@@ -109,30 +277,261 @@ fn copy_file(
 ) -> std::io::Result<()> {
     let exe_name = mk_exe_name(exe_name);
 
-    let mut install_path = PathBuf::new();
-    install_path.push(store_directory);
-    install_path.push(exe_name);
-
-    // Copy file from the downloaded unpacked archive to 'store_directory'
-    let res = fs::copy(&tool_path, &install_path);
-    match res {
-        Ok(_) => {}
-        Err(e) if e.kind().to_string() == "executable file busy" => {
-            fs::remove_file(&install_path)?;
-            fs::copy(&tool_path, &install_path)?;
+    let install_path = store_directory.join(&exe_name);
+    let new_path = store_directory.join(format!(".{}.tool-sync-new", exe_name));
+    let backup_path = store_directory.join(format!(".{}.tool-sync-backup", exe_name));
+
+    let copied =
+        fs::copy(&tool_path, &new_path).and_then(|_| set_executable_permissions(&new_path));
+    if let Err(e) = copied {
+        let _ = fs::remove_file(&new_path);
+        return Err(e);
+    }
+
+    replace_file(&new_path, &install_path, &backup_path)
+}
+
+fn copy_extra(
+    path: &Path,
+    directory: &Path,
+    file_name: &str,
+) -> std::io::Result<()> {
+    fs::create_dir_all(directory)?;
+    fs::copy(path, directory.join(file_name)).map(|_| ())
+}
+
+/// Section of the man page from its file name, e.g. "5" for "bat.5" or
+/// "rg.1.gz". Defaults to general commands.
+fn man_section(file_name: &str) -> &str {
+    let file_name = file_name.trim_end_matches(".gz");
+
+    match file_name.rsplit_once('.') {
+        Some((_, extension)) if extension.starts_with(|c: char| c.is_ascii_digit()) => {
+            &extension[..1]
         }
-        Err(e) => return Err(e),
+        _ => "1",
     }
+}
 
-    set_executable_permissions(&install_path);
+/// Point the executable in 'store_directory' to the one in
+/// 'version_directory'. The link is created next to the old executable first
+/// and then renamed over it to switch versions atomically.
+pub fn link_file(
+    version_directory: &Path,
+    store_directory: &Path,
+    exe_name: &str,
+) -> std::io::Result<()> {
+    let exe_name = mk_exe_name(exe_name);
 
-    Ok(())
+    let target_path = version_directory.join(&exe_name);
+    let install_path = store_directory.join(&exe_name);
+    let new_path = store_directory.join(format!(".{}.tool-sync-new", exe_name));
+
+    let _ = fs::remove_file(&new_path);
+    symlink_file(&target_path, &new_path)?;
+
+    fs::rename(&new_path, &install_path).inspect_err(|_| {
+        let _ = fs::remove_file(&new_path);
+    })
+}
+
+#[cfg(target_family = "unix")]
+fn symlink_file(
+    target_path: &Path,
+    link_path: &Path,
+) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target_path, link_path)
+}
+
+/// Creating symlinks requires special privileges on Windows, so the
+/// executable is copied instead
+#[cfg(target_family = "windows")]
+fn symlink_file(
+    target_path: &Path,
+    link_path: &Path,
+) -> std::io::Result<()> {
+    fs::copy(target_path, link_path).map(|_| ())
+}
+
+/// Move 'new_path' to 'install_path', keeping the previous file in
+/// 'backup_path' until the new one is in place and restoring it on failure
+fn replace_file(
+    new_path: &Path,
+    install_path: &Path,
+    backup_path: &Path,
+) -> std::io::Result<()> {
+    let _ = fs::remove_file(backup_path);
+
+    let has_previous = install_path.is_file();
+    if has_previous && let Err(e) = backup_file(install_path, backup_path) {
+        let _ = fs::remove_file(new_path);
+        return Err(e);
+    }
+
+    match fs::rename(new_path, install_path) {
+        Ok(()) => {
+            // the backup of a running executable can't be removed on Windows,
+            // it's removed during the next install instead
+            let _ = fs::remove_file(backup_path);
+            Ok(())
+        }
+        Err(e) => {
+            let _ = fs::remove_file(new_path);
+            if has_previous && !install_path.exists() {
+                let _ = fs::rename(backup_path, install_path);
+            }
+            Err(e)
+        }
+    }
 }
 
+/// The previous executable stays in place until it's atomically replaced
+#[cfg(target_family = "unix")]
+fn backup_file(
+    install_path: &Path,
+    backup_path: &Path,
+) -> std::io::Result<()> {
+    fs::hard_link(install_path, backup_path)
+        .or_else(|_| fs::copy(install_path, backup_path).map(|_| ()))
+}
+
+/// A running executable can't be replaced on Windows but can be renamed
 #[cfg(target_family = "windows")]
-fn set_executable_permissions(_exe_path: &PathBuf) {}
+fn backup_file(
+    install_path: &Path,
+    backup_path: &Path,
+) -> std::io::Result<()> {
+    fs::rename(install_path, backup_path)
+}
+
+#[cfg(target_family = "windows")]
+fn set_executable_permissions(_exe_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
 
 #[cfg(target_family = "unix")]
-fn set_executable_permissions(exe_path: &PathBuf) {
-    fs::set_permissions(exe_path, fs::Permissions::from_mode(0o755)).unwrap();
+fn set_executable_permissions(exe_path: &Path) -> std::io::Result<()> {
+    fs::set_permissions(exe_path, fs::Permissions::from_mode(0o755))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_executable_is_installed() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let tool_path = tmp_dir.path().join("unpacked");
+        fs::write(&tool_path, "new").unwrap();
+
+        let store_directory = tmp_dir.path().join("bin");
+        fs::create_dir(&store_directory).unwrap();
+
+        copy_file(tool_path, &store_directory, "rg").unwrap();
+
+        let installed: Vec<_> = fs::read_dir(&store_directory).unwrap().collect();
+        assert_eq!(installed.len(), 1);
+        assert_eq!(
+            fs::read_to_string(store_directory.join(mk_exe_name("rg"))).unwrap(),
+            "new"
+        );
+    }
+
+    #[test]
+    fn previous_executable_is_replaced() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let tool_path = tmp_dir.path().join("unpacked");
+        fs::write(&tool_path, "new").unwrap();
+
+        let store_directory = tmp_dir.path().join("bin");
+        fs::create_dir(&store_directory).unwrap();
+        fs::write(store_directory.join(mk_exe_name("rg")), "old").unwrap();
+
+        copy_file(tool_path, &store_directory, "rg").unwrap();
+
+        let installed: Vec<_> = fs::read_dir(&store_directory).unwrap().collect();
+        assert_eq!(installed.len(), 1);
+        assert_eq!(
+            fs::read_to_string(store_directory.join(mk_exe_name("rg"))).unwrap(),
+            "new"
+        );
+    }
+
+    #[test]
+    fn failed_copy_keeps_previous_executable() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let tool_path = tmp_dir.path().join("missing");
+
+        let store_directory = tmp_dir.path().join("bin");
+        fs::create_dir(&store_directory).unwrap();
+        fs::write(store_directory.join(mk_exe_name("rg")), "old").unwrap();
+
+        assert!(copy_file(tool_path, &store_directory, "rg").is_err());
+
+        let installed: Vec<_> = fs::read_dir(&store_directory).unwrap().collect();
+        assert_eq!(installed.len(), 1);
+        assert_eq!(
+            fs::read_to_string(store_directory.join(mk_exe_name("rg"))).unwrap(),
+            "old"
+        );
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn linked_version_is_switched() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let store_directory = tmp_dir.path().join("bin");
+        fs::create_dir(&store_directory).unwrap();
+        fs::write(store_directory.join("rg"), "flat").unwrap();
+
+        for (tag, contents) in [("13.0.0", "old"), ("14.0.0", "new")] {
+            let version_directory = tmp_dir.path().join("tools").join("ripgrep").join(tag);
+            fs::create_dir_all(&version_directory).unwrap();
+            fs::write(version_directory.join("rg"), contents).unwrap();
+        }
+
+        let versions_directory = tmp_dir.path().join("tools").join("ripgrep");
+
+        link_file(&versions_directory.join("13.0.0"), &store_directory, "rg").unwrap();
+        assert_eq!(
+            fs::read_to_string(store_directory.join("rg")).unwrap(),
+            "old"
+        );
+
+        link_file(&versions_directory.join("14.0.0"), &store_directory, "rg").unwrap();
+        assert_eq!(
+            fs::read_to_string(store_directory.join("rg")).unwrap(),
+            "new"
+        );
+        assert_eq!(
+            fs::read_link(store_directory.join("rg")).unwrap(),
+            versions_directory.join("14.0.0").join("rg")
+        );
+
+        let installed: Vec<_> = fs::read_dir(&store_directory).unwrap().collect();
+        assert_eq!(installed.len(), 1);
+    }
+
+    #[test]
+    fn man_page_sections() {
+        assert_eq!(man_section("rg.1"), "1");
+        assert_eq!(man_section("bat.5"), "5");
+        assert_eq!(man_section("gh-pr.1.gz"), "1");
+        assert_eq!(man_section("tool.3pm"), "3");
+        assert_eq!(man_section("README"), "1");
+    }
+
+    #[test]
+    fn failed_rename_restores_previous_file() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let new_path = tmp_dir.path().join("new");
+        let install_path = tmp_dir.path().join("rg");
+        let backup_path = tmp_dir.path().join("backup");
+
+        fs::write(&install_path, "old").unwrap();
+
+        // 'new_path' doesn't exist so renaming it fails
+        assert!(replace_file(&new_path, &install_path, &backup_path).is_err());
+        assert_eq!(fs::read_to_string(&install_path).unwrap(), "old");
+    }
 }
@@ -1,37 +1,58 @@
 mod archive;
-mod configure;
+pub mod bundle;
+pub mod configure;
 pub mod db;
 mod download;
 mod install;
+mod offline;
+mod package;
 mod prefetch;
 mod progress;
+pub mod rollback;
+pub mod state;
+pub mod uninstall;
 
-use console::Emoji;
+use console::{Emoji, style};
 use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 use crate::config::schema::{Config, ConfigAsset};
 use crate::config::toml;
+use crate::infra::env_path;
+use crate::infra::rate_limit::RateLimit;
+use crate::infra::retry::RetryPolicy;
+use crate::model::asset_name::mk_exe_name;
+use crate::model::tool::ToolAsset;
 
 use self::install::Installer;
-use self::prefetch::prefetch;
+use self::prefetch::{prefetch, prefetch_offline};
 use self::progress::SyncProgress;
 use self::progress::ToolPair;
+use self::state::InstallState;
 
 pub fn sync_from_path(
     config_path: PathBuf,
     tool: Option<String>,
+    offline: bool,
     proxy: Option<String>,
+    wait_on_rate_limit: bool,
+    create_dirs: bool,
 ) {
-    toml::with_parsed_file(config_path.clone(), proxy, |config| {
-        sync_from_config(config, config_path, tool)
-    });
+    toml::with_parsed_file(
+        config_path.clone(),
+        proxy,
+        wait_on_rate_limit,
+        create_dirs,
+        |config| sync_from_config(config, config_path, tool, offline),
+    );
 }
 
 pub fn sync_from_config(
     mut config: Config,
     config_path: PathBuf,
     tool: Option<String>,
+    offline: bool,
 ) {
     if config.tools.is_empty() {
         no_tools_message();
@@ -40,10 +61,10 @@ pub fn sync_from_config(
 
     match tool {
         Some(tool) => match config.tools.remove(&tool) {
-            Some(asset) => sync_single_tool(config, tool, asset),
+            Some(asset) => sync_single_tool(config, tool, asset, offline),
             None => tool_not_in_config_message(&tool, &config_path),
         },
-        None => sync_from_config_no_check(config),
+        None => sync_from_config_no_check(config, offline),
     }
 }
 
@@ -87,21 +108,40 @@ installing one of the tools natively supported by 'tool-sync'."#,
 
 const DONE: Emoji<'_, '_> = Emoji("✨ ", "* ");
 const DIRECTORY: Emoji<'_, '_> = Emoji("📁 ", "* ");
+const WARNING: Emoji<'_, '_> = Emoji("⚠️  ", "! ");
+const ERROR: Emoji<'_, '_> = Emoji("❌ ", "x ");
 
 pub fn sync_single_tool(
     mut config: Config,
     name: String,
     asset: ConfigAsset,
+    offline: bool,
 ) {
     config.tools = BTreeMap::from([(name, asset)]);
-    sync_from_config_no_check(config);
+    sync_from_config_no_check(config, offline);
 }
 
-/// Like `sync_from_config` but expects non-empty list of tools
-pub fn sync_from_config_no_check(config: Config) {
+/// Like `sync_from_config` but expects non-empty list of tools. In the
+/// `offline` mode, tools are installed only from the download cache.
+pub fn sync_from_config_no_check(
+    mut config: Config,
+    offline: bool,
+) {
     let store_directory = config.ensure_store_directory();
+    let tool_store_directories = ensure_tool_store_directories(&mut config, &store_directory);
 
-    let tool_assets = prefetch(config.tools);
+    let retry = RetryPolicy::with_retries(config.retries);
+    let rate_limit = Rc::new(RateLimit::new(config.wait_on_rate_limit));
+    let download_cache = config.download_cache();
+    let versions_directory = config.versions_directory();
+    let extras_directories = config.extras_directories.expand();
+    let mut install_state = InstallState::load();
+
+    let tool_assets = if offline {
+        prefetch_offline(config.tools, &install_state, download_cache.as_ref())
+    } else {
+        prefetch(config.tools, retry, &rate_limit)
+    };
     if tool_assets.is_empty() {
         empty_prefetched_tool_assets_message();
         return;
@@ -116,23 +156,125 @@ pub fn sync_from_config_no_check(config: Config) {
         .collect();
 
     let sync_progress = SyncProgress::new(tool_pairs);
-    let installer = Installer::mk(store_directory.as_path(), sync_progress);
+    let installer = Installer::mk(
+        sync_progress,
+        download_cache,
+        offline,
+        versions_directory,
+        extras_directories,
+    );
 
     let mut installed_tools: u64 = 0;
+    let mut installed_executables: Vec<(String, PathBuf)> = Vec::new();
 
     for tool_asset in tool_assets {
-        let is_success = installer.install(tool_asset);
+        let tool_store_directory = tool_store_directories
+            .get(&tool_asset.tool_name)
+            .unwrap_or(&store_directory);
+
+        if tool_asset.is_channel && install_state.is_up_to_date(&tool_asset, tool_store_directory) {
+            installer.up_to_date(&tool_asset);
+            installed_tools += 1;
+            installed_executables.extend(exe_paths(&tool_asset, tool_store_directory));
+            continue;
+        }
+
+        let is_success = installer.install(&tool_asset, tool_store_directory);
         if is_success {
-            installed_tools += 1
+            remove_moved_executables(&install_state, &tool_asset, tool_store_directory);
+            install_state.record(&tool_asset, tool_store_directory);
+            installed_tools += 1;
+            installed_executables.extend(exe_paths(&tool_asset, tool_store_directory));
         }
     }
 
-    summary_message(installed_tools, store_directory);
+    if let Err(e) = install_state.save() {
+        eprintln!("{} Unable to save the install state: {}", WARNING, e);
+    }
+
+    summary_message(installed_tools, store_directory, &tool_store_directories);
+    shadowed_executables_warning(&installed_executables);
+}
+
+/// Resolve store directories of tools that override the global one. Tools
+/// whose directory can't be used are reported and removed from the config.
+fn ensure_tool_store_directories(
+    config: &mut Config,
+    store_directory: &Path,
+) -> BTreeMap<String, PathBuf> {
+    let mut tool_store_directories = BTreeMap::new();
+    let create = config.create_store_directory;
+
+    config.tools.retain(|tool_name, config_asset| {
+        match config_asset.ensure_store_directory(store_directory, create) {
+            None => true,
+            Some(Ok(tool_store_directory)) => {
+                tool_store_directories.insert(tool_name.clone(), tool_store_directory);
+                true
+            }
+            Some(Err(e)) => {
+                eprintln!("{} {} {}", ERROR, style(tool_name).cyan().bold(), e);
+                false
+            }
+        }
+    });
+
+    tool_store_directories
+}
+
+/// Remove executables of the tool from the directory it was installed into
+/// before, if it's now installed into another one
+fn remove_moved_executables(
+    install_state: &InstallState,
+    tool_asset: &ToolAsset,
+    store_directory: &Path,
+) {
+    if let Some(installed) = install_state.tools.get(&tool_asset.tool_name)
+        && installed.store_directory != store_directory
+    {
+        uninstall::remove_executables(&tool_asset.tool_name, installed);
+    }
+}
+
+/// Paths of all installed executables of the tool
+fn exe_paths(
+    tool_asset: &ToolAsset,
+    store_directory: &Path,
+) -> Vec<(String, PathBuf)> {
+    tool_asset
+        .executables
+        .iter()
+        .map(|exe| {
+            (
+                tool_asset.tool_name.clone(),
+                store_directory.join(mk_exe_name(&exe.bin_name)),
+            )
+        })
+        .collect()
+}
+
+/// Warn about installed executables that don't run by name because another
+/// executable with the same name comes earlier in $PATH
+fn shadowed_executables_warning(installed_executables: &[(String, PathBuf)]) {
+    let path_dirs = env_path::path_dirs();
+
+    for (tool_name, exe_path) in installed_executables {
+        if let Some(shadowing_path) = env_path::shadowed_by(exe_path, &path_dirs) {
+            eprintln!(
+                "{} {}: {} comes earlier in $PATH and runs instead of {}",
+                WARNING,
+                tool_name,
+                shadowing_path.display(),
+                exe_path.display()
+            );
+        }
+    }
 }
 
 fn summary_message(
     installed_tools: u64,
     store_directory: PathBuf,
+    tool_store_directories: &BTreeMap<String, PathBuf>,
 ) {
     eprintln!(
         "{} Successfully installed {} {}!",
@@ -149,6 +291,14 @@ fn summary_message(
         DIRECTORY,
         store_directory.display()
     );
+    for (tool_name, tool_store_directory) in tool_store_directories {
+        eprintln!(
+            "{} Installation directory of {}: {}",
+            DIRECTORY,
+            tool_name,
+            tool_store_directory.display()
+        );
+    }
 }
 
 fn empty_prefetched_tool_assets_message() {
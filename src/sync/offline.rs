@@ -0,0 +1,215 @@
+//! Resolving tools without network access: versions come from the install
+//! state and assets from the local download cache.
+
+use std::fmt::{Display, Formatter};
+
+use super::state::InstalledTool;
+use crate::infra::cache::DownloadCache;
+use crate::model::release::Asset;
+use crate::model::tool::{ToolInfo, ToolInfoTag};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OfflineError {
+    /// The tool was never installed so its version is unknown
+    NotInstalled,
+
+    /// The tool was installed from a different repository
+    OtherRepo { owner: String, repo: String },
+
+    /// The configured tag differs from the installed one
+    OtherTag {
+        installed: String,
+        requested: String,
+    },
+
+    /// The asset of the installed version isn't in the download cache
+    NotCached { asset_name: String },
+}
+
+impl Display for OfflineError {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            OfflineError::NotInstalled => {
+                write!(
+                    f,
+                    "The tool was never installed, run 'tool sync' with network access first"
+                )
+            }
+            OfflineError::OtherRepo { owner, repo } => write!(
+                f,
+                "The tool was installed from another repository: {}/{}",
+                owner, repo
+            ),
+            OfflineError::OtherTag {
+                installed,
+                requested,
+            } => write!(
+                f,
+                "Requested tag '{}' but only '{}' was installed",
+                requested, installed
+            ),
+            OfflineError::NotCached { asset_name } => {
+                write!(f, "Asset '{}' is not in the download cache", asset_name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for OfflineError {}
+
+/// Find the tag and asset to install for the tool from its previous install
+pub fn resolve_offline(
+    tool_info: &ToolInfo,
+    installed: Option<&InstalledTool>,
+    download_cache: Option<&DownloadCache>,
+) -> Result<(String, Asset), OfflineError> {
+    let installed = installed.ok_or(OfflineError::NotInstalled)?;
+
+    if installed.owner != tool_info.owner || installed.repo != tool_info.repo {
+        return Err(OfflineError::OtherRepo {
+            owner: installed.owner.clone(),
+            repo: installed.repo.clone(),
+        });
+    }
+
+    if let ToolInfoTag::Specific(tag) | ToolInfoTag::Channel(tag) = &tool_info.tag
+        && *tag != installed.tag
+    {
+        return Err(OfflineError::OtherTag {
+            installed: installed.tag.clone(),
+            requested: tag.clone(),
+        });
+    }
+
+    let asset = installed.asset();
+    match download_cache.and_then(|cache| cache.get(&asset)) {
+        Some(_) => Ok((installed.tag.clone(), asset)),
+        None => Err(OfflineError::NotCached {
+            asset_name: asset.name,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+    use std::path::PathBuf;
+
+    use crate::model::asset_name::AssetName;
+    use crate::model::tool::Extras;
+
+    fn mk_tool_info(tag: ToolInfoTag) -> ToolInfo {
+        ToolInfo {
+            owner: String::from("BurntSushi"),
+            repo: String::from("ripgrep"),
+            exe_name: String::from("rg"),
+            bin_name: None,
+            exe_names: vec![],
+            tag,
+            asset_name: AssetName {
+                linux: None,
+                macos: None,
+                windows: None,
+            },
+            exe_path: None,
+            extras: Extras::default(),
+        }
+    }
+
+    fn mk_installed() -> InstalledTool {
+        InstalledTool {
+            owner: String::from("BurntSushi"),
+            repo: String::from("ripgrep"),
+            tag: String::from("13.0.0"),
+            asset_id: 1,
+            asset_name: String::from("ripgrep-13.0.0.tar.gz"),
+            updated_at: Some(String::from("2022-10-01T00:00:00Z")),
+            size: 4,
+            digest: Some(String::from(
+                "sha256:03ac674216f3e15c761ee1a5e255f067953623c8b388b4459e13f978d7c846f4",
+            )),
+            store_directory: PathBuf::from("/tmp"),
+            exe_name: String::from("rg"),
+            executables: vec![],
+            exe_path: None,
+            previous: None,
+        }
+    }
+
+    fn mk_cache(installed: &InstalledTool) -> (tempfile::TempDir, DownloadCache) {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let cache = DownloadCache::new(tmp_dir.path().join("downloads"), 1024);
+
+        let downloaded_path = tmp_dir.path().join("ripgrep.tar.gz");
+        fs::write(&downloaded_path, "1234").unwrap();
+        cache.put(&installed.asset(), &downloaded_path).unwrap();
+
+        (tmp_dir, cache)
+    }
+
+    #[test]
+    fn cached_install_is_resolved() {
+        let installed = mk_installed();
+        let (_tmp_dir, cache) = mk_cache(&installed);
+
+        assert_eq!(
+            resolve_offline(
+                &mk_tool_info(ToolInfoTag::Latest),
+                Some(&installed),
+                Some(&cache)
+            ),
+            Ok((String::from("13.0.0"), installed.asset()))
+        );
+        assert_eq!(
+            resolve_offline(
+                &mk_tool_info(ToolInfoTag::Specific(String::from("13.0.0"))),
+                Some(&installed),
+                Some(&cache)
+            ),
+            Ok((String::from("13.0.0"), installed.asset()))
+        );
+    }
+
+    #[test]
+    fn missing_install_is_not_available() {
+        assert_eq!(
+            resolve_offline(&mk_tool_info(ToolInfoTag::Latest), None, None),
+            Err(OfflineError::NotInstalled)
+        );
+    }
+
+    #[test]
+    fn other_tag_is_not_available() {
+        let installed = mk_installed();
+        let (_tmp_dir, cache) = mk_cache(&installed);
+
+        assert_eq!(
+            resolve_offline(
+                &mk_tool_info(ToolInfoTag::Specific(String::from("14.0.0"))),
+                Some(&installed),
+                Some(&cache)
+            ),
+            Err(OfflineError::OtherTag {
+                installed: String::from("13.0.0"),
+                requested: String::from("14.0.0"),
+            })
+        );
+    }
+
+    #[test]
+    fn uncached_asset_is_not_available() {
+        let installed = mk_installed();
+
+        assert_eq!(
+            resolve_offline(&mk_tool_info(ToolInfoTag::Latest), Some(&installed), None),
+            Err(OfflineError::NotCached {
+                asset_name: String::from("ripgrep-13.0.0.tar.gz"),
+            })
+        );
+    }
+}
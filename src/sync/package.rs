@@ -0,0 +1,416 @@
+//! Extracting files from Debian and RPM packages without 'dpkg' or 'rpm'.
+
+use deko::AnyDecoder;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, ErrorKind, Read};
+use std::path::{Component, Path};
+
+#[cfg(target_family = "unix")]
+use std::os::unix::fs::PermissionsExt;
+
+use super::archive::{normalize_entry_path, unpack_tar_stream};
+
+const AR_MAGIC: &[u8] = b"!<arch>\n";
+const AR_HEADER_LEN: usize = 60;
+
+const RPM_LEAD_LEN: usize = 96;
+const RPM_HEADER_MAGIC: [u8; 4] = [0x8e, 0xad, 0xe8, 0x01];
+
+const CPIO_HEADER_LEN: usize = 110;
+const CPIO_TRAILER: &str = "TRAILER!!!";
+
+const MODE_TYPE_MASK: u32 = 0o170000;
+const MODE_DIR: u32 = 0o040000;
+const MODE_FILE: u32 = 0o100000;
+
+/// Unpack the 'data.tar.*' member of a .deb package and return paths of
+/// unpacked files relative to 'tmp_dir'
+pub fn unpack_deb(
+    deb_path: &Path,
+    tmp_dir: &Path,
+) -> io::Result<Vec<String>> {
+    let mut reader = BufReader::new(File::open(deb_path)?);
+
+    let mut magic = [0u8; AR_MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+    if magic != AR_MAGIC {
+        return Err(invalid_data("not a Debian package"));
+    }
+
+    loop {
+        let mut header = [0u8; AR_HEADER_LEN];
+        if let Err(e) = reader.read_exact(&mut header) {
+            return match e.kind() {
+                ErrorKind::UnexpectedEof => Err(invalid_data("no data archive in the package")),
+                _ => Err(e),
+            };
+        }
+
+        let name = String::from_utf8_lossy(&header[0..16]);
+        let name = name.trim_end().trim_end_matches('/');
+        let size: u64 = String::from_utf8_lossy(&header[48..58])
+            .trim()
+            .parse()
+            .map_err(|_| invalid_data("invalid member size in the package"))?;
+
+        if name.starts_with("data.tar") {
+            let data = (&mut reader).take(size);
+            return unpack_tar_stream(AnyDecoder::new(BufReader::new(data)), tmp_dir);
+        }
+
+        // members are aligned to 2 bytes
+        skip(&mut reader, size + size % 2)?;
+    }
+}
+
+/// Unpack the cpio payload of an .rpm package and return paths of unpacked
+/// files relative to 'tmp_dir'
+pub fn unpack_rpm(
+    rpm_path: &Path,
+    tmp_dir: &Path,
+) -> io::Result<Vec<String>> {
+    let mut reader = BufReader::new(File::open(rpm_path)?);
+
+    let mut lead = [0u8; RPM_LEAD_LEN];
+    reader.read_exact(&mut lead)?;
+    if lead[0..4] != [0xed, 0xab, 0xee, 0xdb] {
+        return Err(invalid_data("not an RPM package"));
+    }
+
+    // the signature header is padded to 8 bytes, the main header isn't
+    let signature_len = skip_rpm_header(&mut reader)?;
+    skip(&mut reader, (8 - signature_len % 8) % 8)?;
+    skip_rpm_header(&mut reader)?;
+
+    unpack_cpio(AnyDecoder::new(reader), tmp_dir)
+}
+
+/// Skip a header structure of an .rpm package and return its length
+fn skip_rpm_header(reader: &mut impl Read) -> io::Result<u64> {
+    let mut header = [0u8; 16];
+    reader.read_exact(&mut header)?;
+    if header[0..4] != RPM_HEADER_MAGIC {
+        return Err(invalid_data("invalid RPM header"));
+    }
+
+    let index_count = u32::from_be_bytes([header[8], header[9], header[10], header[11]]);
+    let data_len = u32::from_be_bytes([header[12], header[13], header[14], header[15]]);
+    let len = 16 * u64::from(index_count) + u64::from(data_len);
+
+    skip(reader, len)?;
+    Ok(16 + len)
+}
+
+/// Unpack regular files and directories of a cpio archive in the "newc"
+/// format used by RPM. Entries pointing outside of 'tmp_dir' are skipped.
+fn unpack_cpio(
+    mut reader: impl Read,
+    tmp_dir: &Path,
+) -> io::Result<Vec<String>> {
+    let mut files = Vec::new();
+
+    // names of hard links without content by their device and inode, the
+    // content comes with the last name of the group
+    let mut hard_links: BTreeMap<(u32, u32, u32), Vec<String>> = BTreeMap::new();
+
+    loop {
+        let mut header = [0u8; CPIO_HEADER_LEN];
+        reader.read_exact(&mut header)?;
+        if &header[0..6] != b"070701" && &header[0..6] != b"070702" {
+            return Err(invalid_data("unsupported cpio format in the package"));
+        }
+
+        let inode = cpio_field(&header, 0)?;
+        let mode = cpio_field(&header, 1)?;
+        let links = cpio_field(&header, 4)?;
+        let file_size = u64::from(cpio_field(&header, 6)?);
+        let device = (cpio_field(&header, 7)?, cpio_field(&header, 8)?);
+        let name_size = u64::from(cpio_field(&header, 11)?);
+
+        let mut name = vec![0u8; name_size as usize];
+        reader.read_exact(&mut name)?;
+        let name = String::from_utf8_lossy(&name);
+        let name = name.trim_end_matches('\0');
+
+        // the header with the name and the file content are aligned to 4 bytes
+        skip(&mut reader, padding(CPIO_HEADER_LEN as u64 + name_size))?;
+        if name == CPIO_TRAILER {
+            return Ok(files);
+        }
+
+        let is_outside = Path::new(name)
+            .components()
+            .any(|component| component == Component::ParentDir);
+        let path = normalize_entry_path(Path::new(name));
+        let mut content = (&mut reader).take(file_size);
+
+        match mode & MODE_TYPE_MASK {
+            _ if is_outside || path.is_empty() => {}
+            MODE_DIR => fs::create_dir_all(tmp_dir.join(&path))?,
+            MODE_FILE if links > 1 && file_size == 0 => {
+                hard_links
+                    .entry((device.0, device.1, inode))
+                    .or_default()
+                    .push(path);
+            }
+            MODE_FILE => {
+                let file_path = tmp_dir.join(&path);
+                write_file(&mut content, &file_path, mode)?;
+                files.push(path);
+
+                let link_paths = hard_links
+                    .remove(&(device.0, device.1, inode))
+                    .unwrap_or_default();
+                for link_path in link_paths {
+                    let link_file_path = tmp_dir.join(&link_path);
+                    write_file(&mut File::open(&file_path)?, &link_file_path, mode)?;
+                    files.push(link_path);
+                }
+            }
+            // symbolic links and special files aren't needed
+            _ => {}
+        }
+
+        io::copy(&mut content, &mut io::sink())?;
+        skip(&mut reader, padding(file_size))?;
+    }
+}
+
+fn write_file(
+    content: &mut impl Read,
+    file_path: &Path,
+    mode: u32,
+) -> io::Result<()> {
+    if let Some(parent) = file_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    io::copy(content, &mut File::create(file_path)?)?;
+    set_permissions(file_path, mode)
+}
+
+/// Parse the hexadecimal field of a "newc" cpio header by its index
+fn cpio_field(
+    header: &[u8; CPIO_HEADER_LEN],
+    index: usize,
+) -> io::Result<u32> {
+    let start = 6 + index * 8;
+    std::str::from_utf8(&header[start..start + 8])
+        .ok()
+        .and_then(|field| u32::from_str_radix(field, 16).ok())
+        .ok_or_else(|| invalid_data("invalid cpio header in the package"))
+}
+
+fn padding(len: u64) -> u64 {
+    (4 - len % 4) % 4
+}
+
+fn skip(
+    reader: &mut impl Read,
+    len: u64,
+) -> io::Result<()> {
+    let skipped = io::copy(&mut reader.take(len), &mut io::sink())?;
+    if skipped < len {
+        return Err(ErrorKind::UnexpectedEof.into());
+    }
+    Ok(())
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(target_family = "unix")]
+fn set_permissions(
+    path: &Path,
+    mode: u32,
+) -> io::Result<()> {
+    fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o7777))
+}
+
+#[cfg(target_family = "windows")]
+fn set_permissions(
+    _path: &Path,
+    _mode: u32,
+) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::sync::archive::gzip;
+
+    fn ar_member(
+        name: &str,
+        content: &[u8],
+    ) -> Vec<u8> {
+        let mut member = format!(
+            "{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n",
+            name,
+            0,
+            0,
+            0,
+            "100644",
+            content.len()
+        )
+        .into_bytes();
+        member.extend_from_slice(content);
+        if content.len() % 2 == 1 {
+            member.push(b'\n');
+        }
+        member
+    }
+
+    fn cpio_entry(
+        name: &str,
+        mode: u32,
+        content: &[u8],
+    ) -> Vec<u8> {
+        cpio_link(name, mode, content, 0, 1)
+    }
+
+    fn cpio_link(
+        name: &str,
+        mode: u32,
+        content: &[u8],
+        inode: u32,
+        links: u32,
+    ) -> Vec<u8> {
+        let fields = [
+            inode,
+            mode,
+            0,
+            0,
+            links,
+            0,
+            content.len() as u32,
+            0,
+            0,
+            0,
+            0,
+        ];
+        let mut entry = String::from("070701");
+        for field in fields {
+            entry.push_str(&format!("{:08x}", field));
+        }
+        entry.push_str(&format!("{:08x}{:08x}", name.len() + 1, 0));
+
+        let mut entry = entry.into_bytes();
+        entry.extend_from_slice(name.as_bytes());
+        entry.push(0);
+        entry.resize(entry.len() + padding(entry.len() as u64) as usize, 0);
+        entry.extend_from_slice(content);
+        entry.resize(entry.len() + padding(content.len() as u64) as usize, 0);
+        entry
+    }
+
+    fn rpm_header(data_len: u32) -> Vec<u8> {
+        let mut header = RPM_HEADER_MAGIC.to_vec();
+        header.extend_from_slice(&[0, 0, 0, 0]);
+        header.extend_from_slice(&0u32.to_be_bytes());
+        header.extend_from_slice(&data_len.to_be_bytes());
+        header.resize(header.len() + data_len as usize, 0);
+        header
+    }
+
+    #[test]
+    fn deb_data_is_unpacked() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+
+        let mut data = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(9);
+        header.set_mode(0o755);
+        header.set_cksum();
+        data.append_data(&mut header, "./usr/bin/tool", &b"#!/bin/sh"[..])
+            .unwrap();
+        let data = data.into_inner().unwrap();
+
+        let mut deb = AR_MAGIC.to_vec();
+        deb.extend(ar_member("debian-binary", b"2.0\n"));
+        deb.extend(ar_member("control.tar.gz", &gzip(b"control")));
+        deb.extend(ar_member("data.tar.gz", &gzip(&data)));
+
+        let deb_path = tmp_dir.path().join("tool_1.0_amd64.deb");
+        fs::write(&deb_path, deb).unwrap();
+
+        let unpack_dir = tmp_dir.path().join("unpacked");
+        fs::create_dir(&unpack_dir).unwrap();
+
+        let files = unpack_deb(&deb_path, &unpack_dir).unwrap();
+
+        assert_eq!(files, vec!["usr/bin/tool"]);
+        assert_eq!(
+            fs::read(unpack_dir.join("usr/bin/tool")).unwrap(),
+            b"#!/bin/sh"
+        );
+    }
+
+    #[test]
+    fn rpm_payload_is_unpacked() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+
+        let mut cpio = Vec::new();
+        cpio.extend(cpio_entry("./usr", MODE_DIR | 0o755, b""));
+        cpio.extend(cpio_entry(
+            "./usr/bin/tool",
+            MODE_FILE | 0o755,
+            b"#!/bin/sh",
+        ));
+        cpio.extend(cpio_entry("./usr/bin/link", 0o120777, b"tool"));
+        cpio.extend(cpio_entry("../escape", MODE_FILE | 0o644, b"outside"));
+        cpio.extend(cpio_entry(CPIO_TRAILER, 0, b""));
+
+        let mut rpm = vec![0xed, 0xab, 0xee, 0xdb];
+        rpm.resize(RPM_LEAD_LEN, 0);
+        // 16 + 5 bytes of the signature are padded to 24
+        rpm.extend(rpm_header(5));
+        rpm.extend([0, 0, 0]);
+        rpm.extend(rpm_header(7));
+        rpm.extend(gzip(&cpio));
+
+        let rpm_path = tmp_dir.path().join("tool-1.0.x86_64.rpm");
+        fs::write(&rpm_path, rpm).unwrap();
+
+        let unpack_dir = tmp_dir.path().join("unpacked");
+        fs::create_dir(&unpack_dir).unwrap();
+
+        let files = unpack_rpm(&rpm_path, &unpack_dir).unwrap();
+
+        assert_eq!(files, vec!["usr/bin/tool"]);
+        assert_eq!(
+            fs::read(unpack_dir.join("usr/bin/tool")).unwrap(),
+            b"#!/bin/sh"
+        );
+        assert!(!unpack_dir.join("usr/bin/link").exists());
+        assert!(!tmp_dir.path().join("escape").exists());
+        assert!(!unpack_dir.join("escape").exists());
+    }
+
+    #[test]
+    fn cpio_hard_links_are_unpacked() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+
+        // only the last name of a hard link group has the content
+        let mut cpio = Vec::new();
+        cpio.extend(cpio_link("./usr/bin/tool", MODE_FILE | 0o755, b"", 7, 2));
+        cpio.extend(cpio_link(
+            "./usr/bin/tool-alias",
+            MODE_FILE | 0o755,
+            b"#!/bin/sh",
+            7,
+            2,
+        ));
+        cpio.extend(cpio_entry(CPIO_TRAILER, 0, b""));
+
+        let files = unpack_cpio(&cpio[..], tmp_dir.path()).unwrap();
+
+        assert_eq!(files, vec!["usr/bin/tool-alias", "usr/bin/tool"]);
+        for file in files {
+            assert_eq!(fs::read(tmp_dir.path().join(file)).unwrap(), b"#!/bin/sh");
+        }
+    }
+}
@@ -1,15 +1,22 @@
 use console::{Emoji, style};
 use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
 
+use std::cell::Cell;
 use std::collections::BTreeMap;
 use std::fmt::Display;
+use std::rc::Rc;
 
 use super::configure::configure_tool;
+use super::offline::resolve_offline;
+use super::state::InstallState;
 use crate::config::schema::ConfigAsset;
+use crate::infra::cache::DownloadCache;
 use crate::infra::client::Client;
+use crate::infra::rate_limit::{RateLimit, RateLimitExceeded};
+use crate::infra::retry::RetryPolicy;
 use crate::model::release::AssetError;
 use crate::model::repo::RepoError;
-use crate::model::tool::{Tool, ToolAsset};
+use crate::model::tool::{Tool, ToolAsset, ToolInfoTag};
 
 const PREFETCH: Emoji<'_, '_> = Emoji("🔄 ", "-> ");
 const ERROR: Emoji<'_, '_> = Emoji("❌ ", "x ");
@@ -18,12 +25,19 @@ const PACKAGE: Emoji<'_, '_> = Emoji("📦 ", "# ");
 struct PrefetchProgress {
     pb: ProgressBar,
     total_count: usize,
+
+    /// The rate limit error is the same for all tools so it's shown only once
+    is_rate_limit_reported: Cell<bool>,
 }
 
 impl PrefetchProgress {
     fn new(total_count: usize) -> PrefetchProgress {
         let pb = create_prefetch_progress_bar();
-        PrefetchProgress { pb, total_count }
+        PrefetchProgress {
+            pb,
+            total_count,
+            is_rate_limit_reported: Cell::new(false),
+        }
     }
 
     fn update_message(
@@ -75,6 +89,15 @@ don't hesitate to open an issue:
         self.pb.println(err_msg);
     }
 
+    fn rate_limit_err_msg(
+        &self,
+        err: &RateLimitExceeded,
+    ) {
+        if !self.is_rate_limit_reported.replace(true) {
+            self.pb.println(format!("{} {}", ERROR, err))
+        }
+    }
+
     fn finish(&self) {
         self.pb.finish()
     }
@@ -85,7 +108,11 @@ don't hesitate to open an issue:
 ///
 ///   1. Resolving all the required fields from `ConfigAsset`.
 ///   2. Fetching release and asset info from GitHub.
-pub fn prefetch(tools: BTreeMap<String, ConfigAsset>) -> Vec<ToolAsset> {
+pub fn prefetch(
+    tools: BTreeMap<String, ConfigAsset>,
+    retry: RetryPolicy,
+    rate_limit: &Rc<RateLimit>,
+) -> Vec<ToolAsset> {
     let total_count = tools.len();
 
     let prefetch_progress = PrefetchProgress::new(total_count);
@@ -102,6 +129,8 @@ pub fn prefetch(tools: BTreeMap<String, ConfigAsset>) -> Vec<ToolAsset> {
                 &prefetch_progress,
                 index,
                 config_asset.proxy.clone(),
+                retry,
+                Rc::clone(rate_limit),
             )
         })
         .collect();
@@ -125,6 +154,8 @@ fn prefetch_tool(
     prefetch_progress: &PrefetchProgress,
     current_index: usize,
     proxy: Option<ureq::Proxy>,
+    retry: RetryPolicy,
+    rate_limit: Rc<RateLimit>,
 ) -> Option<ToolAsset> {
     // indexes start with 0 so we add 1 to calculate already fetched tools
     let already_completed = current_index + 1;
@@ -141,11 +172,22 @@ fn prefetch_tool(
                 repo: tool_info.repo.clone(),
                 version: tool_info.tag.to_str_version(),
                 proxy,
+                retry,
+                rate_limit,
+            };
+
+            let release = match tool_info.tag {
+                ToolInfoTag::Prerelease => client.fetch_newest_release(),
+                _ => client.fetch_release_info(),
             };
 
-            match client.fetch_release_info() {
+            match release {
                 Err(e) => {
-                    if let Some(ureq::Error::Status(404, _)) = e.downcast_ref::<ureq::Error>() {
+                    if let Some(rate_limit_err) = e.downcast_ref::<RateLimitExceeded>() {
+                        prefetch_progress.rate_limit_err_msg(rate_limit_err);
+                    } else if let Some(ureq::Error::Status(404, _)) =
+                        e.downcast_ref::<ureq::Error>()
+                    {
                         prefetch_progress.unexpected_err_msg(
                             tool_name,
                             RepoError::NotFound {
@@ -178,8 +220,11 @@ fn prefetch_tool(
                         let tool_asset = ToolAsset {
                             tool_name: String::from(tool_name),
                             tag: release.tag_name,
-                            exe_name: tool_info.exe_name,
+                            executables: tool_info.executables(),
+                            exe_path: tool_info.exe_path,
+                            extras: tool_info.extras,
                             asset,
+                            is_channel: matches!(tool_info.tag, ToolInfoTag::Channel(_)),
                             client,
                         };
 
@@ -193,6 +238,59 @@ fn prefetch_tool(
     }
 }
 
+/// Resolve all the tools from the configuration using only the install state
+/// and the download cache, without sending any requests to GitHub.
+pub fn prefetch_offline(
+    tools: BTreeMap<String, ConfigAsset>,
+    install_state: &InstallState,
+    download_cache: Option<&DownloadCache>,
+) -> Vec<ToolAsset> {
+    let prefetch_progress = PrefetchProgress::new(tools.len());
+    prefetch_progress.update_message(0);
+
+    let tool_assets = tools
+        .iter()
+        .enumerate()
+        .filter_map(|(index, (tool_name, config_asset))| {
+            let tool_asset = match configure_tool(tool_name, config_asset) {
+                Tool::Error(e) => {
+                    prefetch_progress.expected_err_msg(tool_name, e);
+                    None
+                }
+                Tool::Known(tool_info) => {
+                    let installed = install_state.tools.get(tool_name);
+                    match resolve_offline(&tool_info, installed, download_cache) {
+                        Err(e) => {
+                            prefetch_progress.expected_err_msg(
+                                tool_name,
+                                format!("[not available offline] {}", e),
+                            );
+                            None
+                        }
+                        Ok((tag, asset)) => Some(ToolAsset {
+                            tool_name: String::from(tool_name),
+                            executables: tool_info.executables(),
+                            exe_path: tool_info.exe_path,
+                            extras: tool_info.extras,
+                            asset,
+                            is_channel: matches!(tool_info.tag, ToolInfoTag::Channel(_)),
+                            // assets come from the cache, nothing is requested
+                            client: Client::offline(tool_info.owner, tool_info.repo, &tag),
+                            tag,
+                        }),
+                    }
+                }
+            };
+
+            prefetch_progress.update_message(index + 1);
+            tool_asset
+        })
+        .collect();
+
+    prefetch_progress.finish();
+    tool_assets
+}
+
 fn create_prefetch_progress_bar() -> ProgressBar {
     let message_style = ProgressStyle::with_template("{prefix} {msg}").unwrap();
 
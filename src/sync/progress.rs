@@ -107,6 +107,47 @@ impl SyncProgress {
         pb.finish();
     }
 
+    /// Report success with a problem that didn't fail the installation
+    pub fn success_with_warning<Message: Display>(
+        &self,
+        pb: ProgressBar,
+        tool_name: &str,
+        tag: &str,
+        warning_msg: Message,
+    ) {
+        pb.set_prefix(self.fmt_prefix(SUCCESS, tool_name, tag));
+
+        let success_msg = format!(
+            "{} {}",
+            style("Completed!").bold().green(),
+            style(warning_msg).yellow()
+        );
+        pb.set_message(success_msg);
+        pb.finish();
+    }
+
+    pub fn up_to_date(
+        &self,
+        pb: ProgressBar,
+        tool_name: &str,
+        tag: &str,
+    ) {
+        pb.set_prefix(self.fmt_prefix(SUCCESS, tool_name, tag));
+
+        let up_to_date_msg = format!("{}", style("Already up to date").bold().green());
+        pb.set_message(up_to_date_msg);
+        pb.finish();
+    }
+
+    /// Print a message above the progress bars
+    pub fn println<Message: Display>(
+        &self,
+        msg: Message,
+    ) {
+        // the message is only lost if the terminal is gone
+        let _ = self.multi_progress.println(msg.to_string());
+    }
+
     /// This method can take in any type that implements the [`Display`] trait
     pub fn failure<Message: Display>(
         &self,
@@ -0,0 +1,160 @@
+//! Restoring the previously installed version of a tool from local storage
+//! without network access.
+
+use console::{Emoji, style};
+use std::path::PathBuf;
+
+use super::install::{Installer, link_file};
+use super::progress::{SyncProgress, ToolPair};
+use super::state::{InstallState, InstalledTool, version_directory};
+use super::{DONE, WARNING, shadowed_executables_warning};
+use crate::config::schema::{Config, ExtrasDirectories};
+use crate::config::toml;
+use crate::infra::err;
+use crate::model::asset_name::mk_exe_name;
+
+const PIN: Emoji<'_, '_> = Emoji("📌 ", "* ");
+
+pub fn rollback_from_path(
+    config_path: PathBuf,
+    tool_name: String,
+    pin: bool,
+) {
+    toml::with_parsed_file(config_path.clone(), None, false, false, |config| {
+        let tag = rollback(&config, &tool_name);
+
+        if pin {
+            match toml::pin_tag(&config_path, &tool_name, &tag) {
+                Ok(()) => eprintln!(
+                    "{} Pinned {} to tag {} in {}",
+                    PIN,
+                    tool_name,
+                    tag,
+                    config_path.display()
+                ),
+                Err(e) => err::abort_with(format!(
+                    "Unable to pin the tag in the configuration at path {}: {}",
+                    config_path.display(),
+                    e
+                )),
+            }
+        } else {
+            eprintln!(
+                "{} The next 'tool sync' may install a newer version again. Use 'tool rollback {} --pin' to keep this one.",
+                WARNING, tool_name
+            );
+        }
+    })
+}
+
+/// Restore the previous version of the tool and return its tag
+fn rollback(
+    config: &Config,
+    tool_name: &str,
+) -> String {
+    let mut install_state = InstallState::load();
+
+    let installed = match install_state.tools.get(tool_name) {
+        Some(installed) => installed,
+        None => err::abort_with(format!(
+            "The tool '{}' was never installed by 'tool-sync'",
+            tool_name
+        )),
+    };
+
+    let previous = match &installed.previous {
+        Some(previous) => previous,
+        None => err::abort_with(format!(
+            "No previous version of '{}' is known, only {} was installed",
+            tool_name, installed.tag
+        )),
+    };
+
+    if !restore(config, tool_name, installed, previous) {
+        err::abort_with(format!(
+            "Unable to restore {} {}: it isn't kept locally anymore, sync a specific tag instead",
+            tool_name, previous.tag
+        ));
+    }
+
+    let from_tag = installed.tag.clone();
+    let restored = match install_state.rollback(tool_name) {
+        Some(restored) => restored.clone(),
+        None => err::abort_suggest_issue("The previous version disappeared during the rollback"),
+    };
+
+    if let Err(e) = install_state.save() {
+        eprintln!("{} Unable to save the install state: {}", WARNING, e);
+    }
+
+    eprintln!(
+        "{} Rolled back {} from {} to {}",
+        DONE,
+        tool_name,
+        style(from_tag).bold(),
+        style(&restored.tag).bold()
+    );
+    let installed_executables: Vec<(String, PathBuf)> = restored
+        .exe_paths()
+        .into_iter()
+        .map(|exe_path| (String::from(tool_name), exe_path))
+        .collect();
+    shadowed_executables_warning(&installed_executables);
+
+    restored.tag
+}
+
+/// Put the previous executable where the current one is. The versioned
+/// install layout keeps it unpacked, otherwise it's installed again from the
+/// download cache.
+fn restore(
+    config: &Config,
+    tool_name: &str,
+    installed: &InstalledTool,
+    previous: &InstalledTool,
+) -> bool {
+    let store_directory = &installed.store_directory;
+
+    // channels like "nightly" reuse the same version directory for new assets
+    if previous.tag != installed.tag
+        && let Some(versions_directory) = config.versions_directory()
+    {
+        let version_directory = version_directory(&versions_directory, tool_name, &previous.tag);
+
+        let executables = previous.executables();
+        let is_unpacked = executables
+            .iter()
+            .all(|exe| version_directory.join(mk_exe_name(&exe.bin_name)).is_file());
+
+        if is_unpacked {
+            for exe in executables {
+                if let Err(e) = link_file(&version_directory, store_directory, &exe.bin_name) {
+                    err::abort_with(format!(
+                        "Unable to link {} from {}: {}",
+                        exe.bin_name,
+                        version_directory.display(),
+                        e
+                    ));
+                }
+            }
+            return true;
+        }
+    }
+
+    let tool_asset = previous.to_tool_asset(tool_name);
+    let sync_progress = SyncProgress::new(vec![ToolPair {
+        name: &tool_asset.tool_name,
+        tag: &tool_asset.tag,
+    }]);
+
+    let installer = Installer::mk(
+        sync_progress,
+        config.download_cache(),
+        true,
+        config.versions_directory(),
+        // only executables are restored
+        ExtrasDirectories::default(),
+    );
+
+    installer.install(&tool_asset, store_directory)
+}
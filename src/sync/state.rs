@@ -0,0 +1,376 @@
+//! Information about previously installed tools, persisted between runs in
+//! the 'tool-sync' data directory.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::infra::cache::sanitize;
+use crate::infra::client::Client;
+use crate::model::asset_name::mk_exe_name;
+use crate::model::release::Asset;
+use crate::model::tool::{Executable, Extras, ToolAsset};
+
+const STATE_FILE: &str = "installed.toml";
+const VERSIONS_DIR: &str = "tools";
+
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstallState {
+    #[serde(default)]
+    pub tools: BTreeMap<String, InstalledTool>,
+}
+
+/// What was installed for a single tool during the last successful sync
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstalledTool {
+    /// GitHub repository author
+    pub owner: String,
+
+    /// GitHub repository name
+    pub repo: String,
+
+    /// Git tag of the installed release
+    pub tag: String,
+
+    /// Id of the downloaded asset
+    pub asset_id: u32,
+
+    /// Name of the downloaded asset
+    pub asset_name: String,
+
+    /// Upload time of the downloaded asset
+    pub updated_at: Option<String>,
+
+    /// Size of the downloaded asset in bytes
+    #[serde(default)]
+    pub size: u64,
+
+    /// Digest of the downloaded asset reported by GitHub
+    #[serde(default)]
+    pub digest: Option<String>,
+
+    /// Directory where the executable was copied
+    pub store_directory: PathBuf,
+
+    /// Name of the main installed executable
+    pub exe_name: String,
+
+    /// All installed executables, only 'exe_name' if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub executables: Vec<Executable>,
+
+    /// Configured path of the executable inside the asset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exe_path: Option<String>,
+
+    /// The version installed before this one, restored by 'tool rollback'
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous: Option<Box<InstalledTool>>,
+}
+
+impl InstalledTool {
+    /// The asset this tool was installed from
+    pub fn asset(&self) -> Asset {
+        Asset {
+            id: self.asset_id,
+            name: self.asset_name.clone(),
+            size: self.size,
+            updated_at: self.updated_at.clone(),
+            digest: self.digest.clone(),
+        }
+    }
+
+    /// All installed executables, the main one goes first
+    pub fn executables(&self) -> Vec<Executable> {
+        if self.executables.is_empty() {
+            vec![Executable::new(&self.exe_name)]
+        } else {
+            self.executables.clone()
+        }
+    }
+
+    /// Paths of all installed executables
+    pub fn exe_paths(&self) -> Vec<PathBuf> {
+        self.executables()
+            .iter()
+            .map(|exe| self.store_directory.join(mk_exe_name(&exe.bin_name)))
+            .collect()
+    }
+
+    /// Asset to install this tool again without fetching the release info
+    pub fn to_tool_asset(
+        &self,
+        tool_name: &str,
+    ) -> ToolAsset {
+        ToolAsset {
+            tool_name: String::from(tool_name),
+            tag: self.tag.clone(),
+            executables: self.executables(),
+            exe_path: self.exe_path.clone(),
+            // only executables are restored
+            extras: Extras::default(),
+            asset: self.asset(),
+            is_channel: false,
+            client: Client::offline(self.owner.clone(), self.repo.clone(), &self.tag),
+        }
+    }
+
+    fn is_same_version(
+        &self,
+        tool_asset: &ToolAsset,
+    ) -> bool {
+        self.tag == tool_asset.tag
+            && self.asset_id == tool_asset.asset.id
+            && self.updated_at == tool_asset.asset.updated_at
+    }
+}
+
+#[derive(Debug)]
+pub enum StateError {
+    IO(std::io::Error),
+    Parse(toml::de::Error),
+    Serialize(toml::ser::Error),
+}
+
+impl Display for StateError {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> std::fmt::Result {
+        match self {
+            StateError::IO(e) => write!(f, "[IO Error] {}", e),
+            StateError::Parse(e) => write!(f, "[Parsing Error] {}", e),
+            StateError::Serialize(e) => write!(f, "[Serialize Error] {}", e),
+        }
+    }
+}
+
+/// Location of the install state file
+pub fn state_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("tool-sync").join(STATE_FILE))
+}
+
+/// Directory with all installed versions of all tools for the versioned
+/// install layout
+pub fn versions_path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("tool-sync").join(VERSIONS_DIR))
+}
+
+/// Directory with all installed versions of a single tool
+pub fn tool_versions_directory(
+    versions_directory: &Path,
+    tool_name: &str,
+) -> PathBuf {
+    versions_directory.join(sanitize(tool_name))
+}
+
+/// Directory where a single version of a tool is unpacked, e.g.
+/// '<versions_directory>/ripgrep/13.0.0'
+pub fn version_directory(
+    versions_directory: &Path,
+    tool_name: &str,
+    tag: &str,
+) -> PathBuf {
+    tool_versions_directory(versions_directory, tool_name).join(sanitize(tag))
+}
+
+impl InstallState {
+    /// Read the install state. A missing or unreadable state file means that
+    /// nothing is known about installed tools.
+    pub fn load() -> InstallState {
+        match state_path() {
+            None => InstallState::default(),
+            Some(path) => Self::load_from(&path).unwrap_or_default(),
+        }
+    }
+
+    fn load_from(path: &Path) -> Result<InstallState, StateError> {
+        let contents = fs::read_to_string(path).map_err(StateError::IO)?;
+        toml::from_str(&contents).map_err(StateError::Parse)
+    }
+
+    pub fn save(&self) -> Result<(), StateError> {
+        match state_path() {
+            None => Ok(()),
+            Some(path) => self.save_to(&path),
+        }
+    }
+
+    fn save_to(
+        &self,
+        path: &Path,
+    ) -> Result<(), StateError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(StateError::IO)?;
+        }
+
+        let contents = toml::to_string_pretty(self).map_err(StateError::Serialize)?;
+        fs::write(path, contents).map_err(StateError::IO)
+    }
+
+    /// Remember that the given asset was installed into 'store_directory'.
+    /// The replaced version is kept to make a rollback possible.
+    pub fn record(
+        &mut self,
+        tool_asset: &ToolAsset,
+        store_directory: &Path,
+    ) {
+        let previous = match self.tools.remove(&tool_asset.tool_name) {
+            None => None,
+            Some(installed) if installed.is_same_version(tool_asset) => installed.previous,
+            Some(mut installed) => {
+                installed.previous = None;
+                Some(Box::new(installed))
+            }
+        };
+
+        let installed_tool = InstalledTool {
+            owner: tool_asset.client.owner.clone(),
+            repo: tool_asset.client.repo.clone(),
+            tag: tool_asset.tag.clone(),
+            asset_id: tool_asset.asset.id,
+            asset_name: tool_asset.asset.name.clone(),
+            updated_at: tool_asset.asset.updated_at.clone(),
+            size: tool_asset.asset.size,
+            digest: tool_asset.asset.digest.clone(),
+            store_directory: store_directory.to_path_buf(),
+            exe_name: tool_asset.exe_name().to_owned(),
+            executables: match tool_asset.executables.as_slice() {
+                // a single executable without renaming is fully described by 'exe_name'
+                [exe] if exe.exe_name == exe.bin_name => vec![],
+                executables => executables.to_vec(),
+            },
+            exe_path: tool_asset.exe_path.clone(),
+            previous,
+        };
+
+        self.tools
+            .insert(tool_asset.tool_name.clone(), installed_tool);
+    }
+
+    /// Make the previous version of the tool the installed one. The replaced
+    /// version becomes the previous one, so rolling back twice is a no-op.
+    pub fn rollback(
+        &mut self,
+        tool_name: &str,
+    ) -> Option<&InstalledTool> {
+        let installed = self.tools.get_mut(tool_name)?;
+        let mut previous = installed.previous.take()?;
+
+        // the previous version is restored where the current one is now
+        previous.store_directory = installed.store_directory.clone();
+
+        let current = std::mem::replace(installed, *previous);
+        installed.previous = Some(Box::new(current));
+
+        Some(installed)
+    }
+
+    /// Returns `true` if exactly this asset was already installed into
+    /// 'store_directory' and the executable is still there
+    pub fn is_up_to_date(
+        &self,
+        tool_asset: &ToolAsset,
+        store_directory: &Path,
+    ) -> bool {
+        match self.tools.get(&tool_asset.tool_name) {
+            None => false,
+            Some(installed) => {
+                installed.store_directory == store_directory
+                    && installed.is_same_version(tool_asset)
+                    && installed
+                        .exe_paths()
+                        .iter()
+                        .all(|exe_path| exe_path.is_file())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nightly_asset(
+        id: u32,
+        updated_at: &str,
+    ) -> ToolAsset {
+        ToolAsset {
+            tool_name: String::from("nvim"),
+            tag: String::from("nightly"),
+            executables: vec![Executable::new("nvim")],
+            exe_path: None,
+            extras: Extras::default(),
+            asset: Asset {
+                id,
+                name: String::from("nvim.appimage"),
+                size: 10,
+                updated_at: Some(String::from(updated_at)),
+                digest: None,
+            },
+            is_channel: true,
+            client: Client::offline(String::from("neovim"), String::from("neovim"), "nightly"),
+        }
+    }
+
+    #[test]
+    fn state_roundtrip() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let path = tmp_dir.path().join("state").join(STATE_FILE);
+
+        let mut state = InstallState::default();
+        state.record(&nightly_asset(1, "2022-10-01T00:00:00Z"), tmp_dir.path());
+        state.save_to(&path).unwrap();
+
+        assert_eq!(InstallState::load_from(&path).unwrap(), state);
+    }
+
+    #[test]
+    fn changed_asset_is_not_up_to_date() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        fs::write(tmp_dir.path().join(mk_exe_name("nvim")), "").unwrap();
+
+        let mut state = InstallState::default();
+        state.record(&nightly_asset(1, "2022-10-01T00:00:00Z"), tmp_dir.path());
+
+        assert!(state.is_up_to_date(&nightly_asset(1, "2022-10-01T00:00:00Z"), tmp_dir.path()));
+        assert!(!state.is_up_to_date(&nightly_asset(2, "2022-10-01T00:00:00Z"), tmp_dir.path()));
+        assert!(!state.is_up_to_date(&nightly_asset(1, "2022-10-02T00:00:00Z"), tmp_dir.path()));
+    }
+
+    #[test]
+    fn missing_executable_is_not_up_to_date() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+
+        let mut state = InstallState::default();
+        state.record(&nightly_asset(1, "2022-10-01T00:00:00Z"), tmp_dir.path());
+
+        assert!(!state.is_up_to_date(&nightly_asset(1, "2022-10-01T00:00:00Z"), tmp_dir.path()));
+    }
+
+    #[test]
+    fn replaced_version_is_rolled_back() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+
+        let mut state = InstallState::default();
+        assert_eq!(state.rollback("nvim"), None);
+
+        state.record(&nightly_asset(1, "2022-10-01T00:00:00Z"), tmp_dir.path());
+        assert_eq!(state.rollback("nvim"), None);
+
+        // installing the same version again keeps the previous one
+        state.record(&nightly_asset(2, "2022-10-02T00:00:00Z"), tmp_dir.path());
+        state.record(&nightly_asset(2, "2022-10-02T00:00:00Z"), tmp_dir.path());
+
+        let restored = state.rollback("nvim").unwrap();
+        assert_eq!(restored.asset_id, 1);
+        assert_eq!(restored.previous.as_ref().unwrap().asset_id, 2);
+        assert_eq!(restored.previous.as_ref().unwrap().previous, None);
+
+        let restored = state.rollback("nvim").unwrap();
+        assert_eq!(restored.asset_id, 2);
+        assert_eq!(restored.previous.as_ref().unwrap().asset_id, 1);
+    }
+}
@@ -0,0 +1,90 @@
+//! Removing installed tools from the directories they were installed into.
+
+use console::style;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use super::state::{InstallState, InstalledTool, tool_versions_directory, versions_path};
+use super::{DONE, WARNING};
+use crate::config::schema::Config;
+use crate::config::toml;
+use crate::infra::err;
+
+pub fn uninstall_from_path(
+    config_path: PathBuf,
+    tool_name: String,
+) {
+    toml::with_parsed_file(config_path.clone(), None, false, false, |config| {
+        uninstall(&config, &tool_name);
+    })
+}
+
+/// Remove executables and unpacked versions of the tool and forget it
+fn uninstall(
+    config: &Config,
+    tool_name: &str,
+) {
+    let mut install_state = InstallState::load();
+
+    let installed = match install_state.tools.remove(tool_name) {
+        Some(installed) => installed,
+        None => err::abort_with(format!(
+            "The tool '{}' was never installed by 'tool-sync'",
+            tool_name
+        )),
+    };
+
+    remove_executables(tool_name, &installed);
+
+    if let Some(versions_directory) = versions_path() {
+        let tool_versions_directory = tool_versions_directory(&versions_directory, tool_name);
+        match fs::remove_dir_all(&tool_versions_directory) {
+            Err(e) if e.kind() != ErrorKind::NotFound => eprintln!(
+                "{} Unable to remove {}: {}",
+                WARNING,
+                tool_versions_directory.display(),
+                e
+            ),
+            _ => {}
+        }
+    }
+
+    if let Err(e) = install_state.save() {
+        eprintln!("{} Unable to save the install state: {}", WARNING, e);
+    }
+
+    eprintln!(
+        "{} Uninstalled {} from {}",
+        DONE,
+        style(tool_name).cyan().bold(),
+        installed.store_directory.display()
+    );
+
+    if config.tools.contains_key(tool_name) {
+        eprintln!(
+            "{} The tool is still in the configuration, so the next 'tool sync' installs it again",
+            WARNING
+        );
+    }
+}
+
+/// Remove the executables of the tool from the directory where they were
+/// installed
+pub fn remove_executables(
+    tool_name: &str,
+    installed: &InstalledTool,
+) {
+    for exe_path in installed.exe_paths() {
+        match fs::remove_file(&exe_path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => eprintln!(
+                "{} {}: unable to remove {}: {}",
+                WARNING,
+                tool_name,
+                exe_path.display(),
+                e
+            ),
+            _ => {}
+        }
+    }
+}